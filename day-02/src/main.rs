@@ -1,67 +1,286 @@
-use std::io::{Read, Result};
+use std::io::{Error, ErrorKind, Read, Result};
 
-fn read_input() -> Result<Vec<(String, i32)>> {
-    let mut input_file = std::fs::File::open("input.txt")?;
-    let mut input = String::new();
-    input_file.read_to_string(&mut input)?;
-    let mut commands: Vec<(String, i32)> = vec![];
-    for line in input.lines() {
-        let line_items: Vec<&str> = line.splitn(2, ' ').collect();
-        commands.push((line_items[0].to_owned(), line_items[1].parse().unwrap()));
+mod number;
+mod script;
+mod trajectory;
+
+use number::{BigInt, Number, Overflow};
+use trajectory::Trajectory;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Command {
+    Forward(i32),
+    Up(i32),
+    Down(i32),
+}
+
+impl Command {
+    /// Splits the command back into its direction and distance.
+    fn parts(&self) -> (&'static str, i32) {
+        match *self {
+            Command::Forward(dist) => ("forward", dist),
+            Command::Up(dist) => ("up", dist),
+            Command::Down(dist) => ("down", dist),
+        }
+    }
+}
+
+/// Physics of the submarine. A model gets fed one command after another and keeps track of where
+/// the submarine is. All arithmetic is checked, so a course that leaves the range of `N` is
+/// reported as an `Overflow`.
+trait SubmarineModel<N: Number> {
+    fn apply(&mut self, command: Command) -> std::result::Result<(), Overflow>;
+
+    /// (horizontal pos, depth)
+    fn position(&self) -> (N, N);
+
+    /// Models without an aim report an aim of 0.
+    fn aim(&self) -> N {
+        N::default()
     }
-    Ok(commands)
 }
 
-fn task_1(commands: &Vec<(String, i32)>) {
-    // (horizontal pos, depth)
-    let mut pos = (0, 0);
+/// Model of task 1: `up` and `down` change the depth directly.
+#[derive(Default)]
+struct DirectModel<N> {
+    pos: (N, N),
+}
 
-    for (dir, dist) in commands {
-        match dir.as_str() {
-            "forward" => pos.0 += dist,
-            "up" => pos.1 -= dist,
-            "down" => pos.1 += dist,
-            _ => panic!("unknown direction: {}", dir)
+impl<N: Number> SubmarineModel<N> for DirectModel<N> {
+    fn apply(&mut self, command: Command) -> std::result::Result<(), Overflow> {
+        match command {
+            Command::Forward(dist) => {
+                self.pos.0 = self.pos.0.checked_add(&N::from(dist)).ok_or(Overflow)?
+            }
+            Command::Up(dist) => {
+                self.pos.1 = self.pos.1.checked_sub(&N::from(dist)).ok_or(Overflow)?
+            }
+            Command::Down(dist) => {
+                self.pos.1 = self.pos.1.checked_add(&N::from(dist)).ok_or(Overflow)?
+            }
         }
+        Ok(())
+    }
+
+    fn position(&self) -> (N, N) {
+        self.pos.clone()
+    }
+}
+
+/// Model of task 2: `up` and `down` change the aim, which is applied to the depth when moving
+/// forward.
+#[derive(Default)]
+struct AimModel<N> {
+    pos: (N, N),
+    aim: N,
+}
+
+impl<N: Number> SubmarineModel<N> for AimModel<N> {
+    fn apply(&mut self, command: Command) -> std::result::Result<(), Overflow> {
+        match command {
+            Command::Forward(dist) => {
+                let dist = N::from(dist);
+                let depth_change = dist.checked_mul(&self.aim).ok_or(Overflow)?;
+                self.pos.0 = self.pos.0.checked_add(&dist).ok_or(Overflow)?;
+                self.pos.1 = self.pos.1.checked_add(&depth_change).ok_or(Overflow)?;
+            }
+            Command::Up(dist) => self.aim = self.aim.checked_sub(&N::from(dist)).ok_or(Overflow)?,
+            Command::Down(dist) => {
+                self.aim = self.aim.checked_add(&N::from(dist)).ok_or(Overflow)?
+            }
+        }
+        Ok(())
+    }
+
+    fn position(&self) -> (N, N) {
+        self.pos.clone()
+    }
+
+    fn aim(&self) -> N {
+        self.aim.clone()
+    }
+}
+
+type ModelConstructor<N> = fn() -> Box<dyn SubmarineModel<N>>;
+
+/// Maps names to model constructors, so models can be picked on the command line.
+struct ModelRegistry<N> {
+    models: Vec<(&'static str, ModelConstructor<N>)>,
+}
+
+impl<N: Number> Default for ModelRegistry<N> {
+    fn default() -> Self {
+        let mut registry = ModelRegistry { models: vec![] };
+        registry.register("direct", || Box::<DirectModel<N>>::default());
+        registry.register("aim", || Box::<AimModel<N>>::default());
+        registry
+    }
+}
+
+impl<N: Number> ModelRegistry<N> {
+    /// Registers a model under `name`. A model that is registered later shadows earlier models
+    /// with the same name.
+    fn register(&mut self, name: &'static str, constructor: ModelConstructor<N>) {
+        self.models.push((name, constructor));
     }
 
-    // Alternative using fold
+    fn create(&self, name: &str) -> Option<Box<dyn SubmarineModel<N>>> {
+        self.models
+            .iter()
+            .rev()
+            .find(|(model_name, _)| *model_name == name)
+            .map(|(_, constructor)| constructor())
+    }
+
+    fn names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.models.iter().map(|(name, _)| *name)
+    }
+}
+
+fn read_input() -> Result<Vec<Command>> {
+    let mut input_file = std::fs::File::open("input.txt")?;
+    let mut input = String::new();
+    input_file.read_to_string(&mut input)?;
+    // Plain course files are valid scripts too
+    script::compile(&input).map_err(|err| Error::new(ErrorKind::InvalidData, err))
+}
+
+fn run_model<N: Number>(
+    model: &mut dyn SubmarineModel<N>,
+    commands: &[Command],
+) -> std::result::Result<N, Overflow> {
+    for command in commands {
+        model.apply(*command)?;
+    }
+
+    // Alternative using fold (only for the direct model without overflow checks)
     // let pos = commands
     //     .iter()
-    //     .fold((0, 0), |(x, y), (dir, dist)| match dir.as_str() {
-    //         "forward" => (x + dist, y),
-    //         "up" => (x, y - dist),
-    //         "down" => (x, y + dist),
-    //         _ => panic!("unknown direction: {}", dir),
+    //     .fold((0, 0), |(x, y), command| match command {
+    //         Command::Forward(dist) => (x + dist, y),
+    //         Command::Up(dist) => (x, y - dist),
+    //         Command::Down(dist) => (x, y + dist),
     //     });
 
-    let result = pos.0 * pos.1;
-    println!("Task 1: {}", result);
+    let pos = model.position();
+    pos.0.checked_mul(&pos.1).ok_or(Overflow)
 }
 
-fn task_2(commands: &Vec<(String, i32)>) {
-    // (horizontal pos, depth)
-    let mut pos = (0, 0);
-    let mut aim = 0;
+#[test]
+fn test_run_model() {
+    let commands =
+        script::compile("forward 5\ndown 5\nforward 8\nup 3\ndown 8\nforward 2").unwrap();
+    assert_eq!(
+        run_model(&mut DirectModel::<i64>::default(), &commands),
+        Ok(150)
+    );
+    assert_eq!(
+        run_model(&mut AimModel::<i64>::default(), &commands),
+        Ok(900)
+    );
 
-    for (dir, dist) in commands {
-        match dir.as_str() {
-            "forward" => {
-                pos.0 += dist;
-                pos.1 += dist * aim;
-            },
-            "up" => aim -= dist,
-            "down" => aim += dist,
-            _ => panic!("unknown direction: {}", dir)
-        }
+    // The depth reaches 55 * (2 * 10^9)^2 = 2.2 * 10^20, which is larger than `i64::MAX`
+    let commands = script::compile("repeat 10 {\ndown 2000000000\nforward 2000000000\n}").unwrap();
+    assert_eq!(
+        run_model(&mut AimModel::<i64>::default(), &commands),
+        Err(Overflow)
+    );
+    assert!(run_model(&mut AimModel::<i128>::default(), &commands).is_ok());
+    assert_eq!(
+        run_model(&mut AimModel::<BigInt>::default(), &commands)
+            .unwrap()
+            .to_string(),
+        "4400000000000000000000000000000"
+    );
+}
+
+fn task_1<N: Number>(commands: &[Command]) {
+    match run_model(&mut DirectModel::<N>::default(), commands) {
+        Ok(result) => println!("Task 1: {}", result),
+        Err(err) => println!("Task 1: {} (try --i128 or --big)", err),
+    }
+}
+
+fn task_2<N: Number>(commands: &[Command]) {
+    match run_model(&mut AimModel::<N>::default(), commands) {
+        Ok(result) => println!("Task 2: {}", result),
+        Err(err) => println!("Task 2: {} (try --i128 or --big)", err),
     }
+}
 
-    let result = pos.0 * pos.1;
-    println!("Task 2: {}", result);
+/// Runs a single model and prints its result, or exports its trajectory in the given format.
+fn run_named_model<N: Number>(name: &str, format: Option<&str>, commands: &[Command]) {
+    let registry = ModelRegistry::<N>::default();
+    let Some(mut model) = registry.create(name) else {
+        let names: Vec<_> = registry.names().collect();
+        eprintln!("unknown model: {} (available: {})", name, names.join(", "));
+        std::process::exit(1);
+    };
+    let trajectory = match Trajectory::record(model.as_mut(), commands) {
+        Ok(trajectory) => trajectory,
+        Err(err) => {
+            eprintln!("{}: {}", name, err);
+            std::process::exit(1);
+        }
+    };
+
+    match format {
+        Some("csv") => print!("{}", trajectory.to_csv()),
+        Some("json") => print!("{}", trajectory.to_json()),
+        Some(format) => {
+            eprintln!("unknown export format: {} (available: csv, json)", format);
+            std::process::exit(1);
+        }
+        None => {
+            let (horizontal, depth) = model.position();
+            match horizontal.checked_mul(&depth) {
+                Some(result) => println!("{}: {}", name, result),
+                None => println!("{}: {}", name, Overflow),
+            }
+            if let Some((step, max_depth)) = trajectory.max_depth() {
+                println!("Max depth: {} (step {})", max_depth, step);
+            }
+        }
+    }
 }
 
 fn main() {
     let commands = read_input().expect("error reading input");
-    task_1(&commands);
-    task_2(&commands);
+
+    // `cargo run -- [--i128|--big] [<model> [csv|json]]` runs only the given model and optionally
+    // exports its trajectory. Positions are 64 bit integers by default, for both tasks as well.
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let precision = match args.first().map(String::as_str) {
+        Some(flag @ ("--i64" | "--i128" | "--big")) => {
+            let flag = flag.to_owned();
+            args.remove(0);
+            flag
+        }
+        _ => String::from("--i64"),
+    };
+
+    if let Some(name) = args.first() {
+        let format = args.get(1).map(String::as_str);
+        match precision.as_str() {
+            "--i128" => run_named_model::<i128>(name, format, &commands),
+            "--big" => run_named_model::<BigInt>(name, format, &commands),
+            _ => run_named_model::<i64>(name, format, &commands),
+        }
+        return;
+    }
+
+    match precision.as_str() {
+        "--i128" => {
+            task_1::<i128>(&commands);
+            task_2::<i128>(&commands);
+        }
+        "--big" => {
+            task_1::<BigInt>(&commands);
+            task_2::<BigInt>(&commands);
+        }
+        _ => {
+            task_1::<i64>(&commands);
+            task_2::<i64>(&commands);
+        }
+    }
 }
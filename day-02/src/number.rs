@@ -0,0 +1,236 @@
+//! Number types the submarine models can compute with. Every operation is checked, so long
+//! courses report an overflow instead of silently wrapping around in release builds.
+
+use std::cmp::Ordering;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Overflow;
+
+impl fmt::Display for Overflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "arithmetic overflow")
+    }
+}
+
+impl std::error::Error for Overflow {}
+
+pub trait Number: Clone + Default + Ord + fmt::Display + From<i32> + 'static {
+    fn checked_add(&self, rhs: &Self) -> Option<Self>;
+    fn checked_sub(&self, rhs: &Self) -> Option<Self>;
+    fn checked_mul(&self, rhs: &Self) -> Option<Self>;
+}
+
+macro_rules! impl_number {
+    ($($t:ty),*) => {
+        $(
+            impl Number for $t {
+                fn checked_add(&self, rhs: &Self) -> Option<Self> {
+                    <$t>::checked_add(*self, *rhs)
+                }
+
+                fn checked_sub(&self, rhs: &Self) -> Option<Self> {
+                    <$t>::checked_sub(*self, *rhs)
+                }
+
+                fn checked_mul(&self, rhs: &Self) -> Option<Self> {
+                    <$t>::checked_mul(*self, *rhs)
+                }
+            }
+        )*
+    };
+}
+
+impl_number!(i64, i128);
+
+/// Arbitrary precision integer. The operations never overflow (as long as there is memory).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BigInt {
+    negative: bool,
+    /// Base 2^32 digits, least significant first, without leading zeros. Zero has no digits and
+    /// is never negative.
+    digits: Vec<u32>,
+}
+
+impl From<i32> for BigInt {
+    fn from(num: i32) -> Self {
+        let mut digits = vec![num.unsigned_abs()];
+        trim(&mut digits);
+        BigInt {
+            negative: num < 0,
+            digits,
+        }
+    }
+}
+
+fn trim(digits: &mut Vec<u32>) {
+    while digits.last() == Some(&0) {
+        digits.pop();
+    }
+}
+
+fn cmp_magnitude(a: &[u32], b: &[u32]) -> Ordering {
+    a.len()
+        .cmp(&b.len())
+        .then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn add_magnitude(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut sum = Vec::with_capacity(a.len().max(b.len()) + 1);
+    let mut carry = 0;
+    for i in 0..a.len().max(b.len()) {
+        let digit_sum = carry + *a.get(i).unwrap_or(&0) as u64 + *b.get(i).unwrap_or(&0) as u64;
+        sum.push(digit_sum as u32);
+        carry = digit_sum >> 32;
+    }
+    sum.push(carry as u32);
+    trim(&mut sum);
+    sum
+}
+
+/// `a` has to be at least as large as `b`
+fn sub_magnitude(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut diff = Vec::with_capacity(a.len());
+    let mut borrow = 0;
+    for (i, &digit) in a.iter().enumerate() {
+        let sub = *b.get(i).unwrap_or(&0) as i64 + borrow;
+        let mut digit_diff = digit as i64 - sub;
+        borrow = 0;
+        if digit_diff < 0 {
+            digit_diff += 1 << 32;
+            borrow = 1;
+        }
+        diff.push(digit_diff as u32);
+    }
+    trim(&mut diff);
+    diff
+}
+
+fn mul_magnitude(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut product = vec![0u32; a.len() + b.len()];
+    for (i, &x) in a.iter().enumerate() {
+        let mut carry = 0u64;
+        for (j, &y) in b.iter().enumerate() {
+            let cur = product[i + j] as u64 + x as u64 * y as u64 + carry;
+            product[i + j] = cur as u32;
+            carry = cur >> 32;
+        }
+        product[i + b.len()] = carry as u32;
+    }
+    trim(&mut product);
+    product
+}
+
+impl BigInt {
+    fn new(negative: bool, digits: Vec<u32>) -> Self {
+        // Normalize negative zero
+        let negative = negative && !digits.is_empty();
+        BigInt { negative, digits }
+    }
+
+    fn neg(&self) -> Self {
+        BigInt::new(!self.negative, self.digits.clone())
+    }
+}
+
+impl Number for BigInt {
+    fn checked_add(&self, rhs: &Self) -> Option<Self> {
+        if self.negative == rhs.negative {
+            return Some(BigInt::new(
+                self.negative,
+                add_magnitude(&self.digits, &rhs.digits),
+            ));
+        }
+
+        // Signs differ, so the smaller magnitude is subtracted from the larger one, which also
+        // determines the sign of the result.
+        let sum = match cmp_magnitude(&self.digits, &rhs.digits) {
+            Ordering::Less => BigInt::new(rhs.negative, sub_magnitude(&rhs.digits, &self.digits)),
+            _ => BigInt::new(self.negative, sub_magnitude(&self.digits, &rhs.digits)),
+        };
+        Some(sum)
+    }
+
+    fn checked_sub(&self, rhs: &Self) -> Option<Self> {
+        self.checked_add(&rhs.neg())
+    }
+
+    fn checked_mul(&self, rhs: &Self) -> Option<Self> {
+        Some(BigInt::new(
+            self.negative != rhs.negative,
+            mul_magnitude(&self.digits, &rhs.digits),
+        ))
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => cmp_magnitude(&self.digits, &other.digits),
+            (true, true) => cmp_magnitude(&other.digits, &self.digits),
+        }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.digits.is_empty() {
+            return write!(f, "0");
+        }
+
+        // Repeatedly divide by 10^9 to get chunks of 9 decimal digits, least significant first
+        const CHUNK: u64 = 1_000_000_000;
+        let mut rest = self.digits.clone();
+        let mut chunks = vec![];
+        while !rest.is_empty() {
+            let mut remainder = 0u64;
+            for digit in rest.iter_mut().rev() {
+                let cur = (remainder << 32) | *digit as u64;
+                *digit = (cur / CHUNK) as u32;
+                remainder = cur % CHUNK;
+            }
+            trim(&mut rest);
+            chunks.push(remainder);
+        }
+
+        if self.negative {
+            write!(f, "-")?;
+        }
+        let mut chunks_iter = chunks.iter().rev();
+        write!(f, "{}", chunks_iter.next().unwrap())?;
+        for chunk in chunks_iter {
+            write!(f, "{:09}", chunk)?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_big_int() {
+    let big = |n: i32| BigInt::from(n);
+    // (2^31 - 1)^3 doesn't fit into an i64
+    let cube = big(i32::MAX)
+        .checked_mul(&big(i32::MAX))
+        .unwrap()
+        .checked_mul(&big(-i32::MAX))
+        .unwrap();
+    assert_eq!(cube.to_string(), "-9903520300447984150353281023");
+    assert_eq!(
+        cube.checked_sub(&cube)
+            .unwrap()
+            .checked_add(&big(-7))
+            .unwrap(),
+        big(-7)
+    );
+    assert_eq!(big(5).checked_sub(&big(12)).unwrap(), big(-7));
+    assert!(big(-3) < big(2) && big(-3) > big(-4));
+    assert_eq!(big(0).checked_mul(&big(-3)).unwrap().to_string(), "0");
+}
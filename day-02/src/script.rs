@@ -0,0 +1,336 @@
+//! A small scripting language on top of the course commands. Plain course files are valid
+//! scripts, but scripts can also use comments, variables, loops and macros:
+//!
+//! ```text
+//! # Comments start with a `#`
+//! let depth = 5
+//! macro dive {
+//!     down depth
+//!     forward 2
+//! }
+//! repeat 3 {
+//!     dive
+//!     up 1
+//! }
+//! ```
+//!
+//! Scripts are compiled down to the flat list of commands. Variables are looked up when a
+//! statement is run, so macros see the variables that are set at the time they are called.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::Command;
+
+/// Stops runaway scripts, like a macro calling itself.
+const MAX_CALL_DEPTH: usize = 64;
+/// Stops scripts whose nested loops would expand to more commands than fit into memory.
+const MAX_COMMANDS: usize = 10_000_000;
+/// Stops scripts whose nested loops run for too long without producing commands. Every statement
+/// and every loop iteration is a step.
+const MAX_STEPS: usize = 4 * MAX_COMMANDS;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ScriptError {
+    /// One based line number in the source
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ScriptError {}
+
+fn error<T>(line: usize, message: impl Into<String>) -> Result<T, ScriptError> {
+    Err(ScriptError {
+        line,
+        message: message.into(),
+    })
+}
+
+#[derive(Debug, Clone)]
+enum Value {
+    Literal(i32),
+    Variable(String),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Direction {
+    Forward,
+    Up,
+    Down,
+}
+
+#[derive(Debug)]
+enum Statement {
+    Move(Direction, Value),
+    Let(String, Value),
+    Repeat(Value, Block),
+    Call(String),
+}
+
+/// A statement together with its line number
+type Block = Vec<(usize, Statement)>;
+
+struct Program {
+    main: Block,
+    macros: HashMap<String, Block>,
+}
+
+pub fn compile(source: &str) -> Result<Vec<Command>, ScriptError> {
+    let program = parse(source)?;
+    let mut interpreter = Interpreter {
+        macros: &program.macros,
+        variables: HashMap::new(),
+        commands: vec![],
+        call_depth: 0,
+        steps: 0,
+    };
+    interpreter.run(&program.main)?;
+    Ok(interpreter.commands)
+}
+
+fn is_identifier(word: &str) -> bool {
+    let mut chars = word.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn is_keyword(word: &str) -> bool {
+    matches!(word, "forward" | "up" | "down" | "let" | "repeat" | "macro")
+}
+
+fn parse_value(line: usize, word: &str) -> Result<Value, ScriptError> {
+    if let Ok(num) = word.parse() {
+        Ok(Value::Literal(num))
+    } else if is_identifier(word) && !is_keyword(word) {
+        Ok(Value::Variable(word.to_owned()))
+    } else {
+        error(
+            line,
+            format!("expected a number or variable, found `{}`", word),
+        )
+    }
+}
+
+fn parse_name(line: usize, word: Option<&str>, what: &str) -> Result<String, ScriptError> {
+    match word {
+        Some(word) if is_identifier(word) && !is_keyword(word) => Ok(word.to_owned()),
+        Some(word) => error(line, format!("invalid {} name `{}`", what, word)),
+        None => error(line, format!("missing {} name", what)),
+    }
+}
+
+fn parse(source: &str) -> Result<Program, ScriptError> {
+    // Stack of open blocks. The first entry is the main block, every other entry is a `repeat`
+    // or `macro` block waiting for its closing brace.
+    enum Open {
+        Main,
+        Repeat(Value),
+        Macro(String),
+    }
+    let mut stack: Vec<(usize, Open, Block)> = vec![(0, Open::Main, vec![])];
+    let mut macros = HashMap::new();
+
+    for (idx, line) in source.lines().enumerate() {
+        let line_num = idx + 1;
+        let code = match line.split_once('#') {
+            Some((code, _comment)) => code,
+            None => line,
+        };
+        let words: Vec<&str> = code.split_whitespace().collect();
+
+        let statement = match words.as_slice() {
+            [] => continue,
+            ["}"] => {
+                let (start, open, block) = stack.pop().unwrap();
+                match open {
+                    Open::Main => return error(line_num, "unmatched `}`"),
+                    Open::Repeat(count) => {
+                        // Errors in the loop header should point at the `repeat` line
+                        let statement = Statement::Repeat(count, block);
+                        stack.last_mut().unwrap().2.push((start, statement));
+                        continue;
+                    }
+                    Open::Macro(name) => {
+                        if macros.insert(name.clone(), block).is_some() {
+                            return error(start, format!("macro `{}` is defined twice", name));
+                        }
+                        continue;
+                    }
+                }
+            }
+            [dir @ ("forward" | "up" | "down"), value] => {
+                let dir = match *dir {
+                    "forward" => Direction::Forward,
+                    "up" => Direction::Up,
+                    _ => Direction::Down,
+                };
+                Statement::Move(dir, parse_value(line_num, value)?)
+            }
+            ["forward" | "up" | "down", ..] => {
+                return error(
+                    line_num,
+                    "expected exactly one distance after the direction",
+                )
+            }
+            ["let", name, "=", value] => {
+                let name = parse_name(line_num, Some(name), "variable")?;
+                Statement::Let(name, parse_value(line_num, value)?)
+            }
+            ["let", ..] => return error(line_num, "expected `let <name> = <value>`"),
+            ["repeat", count, "{"] => {
+                let count = parse_value(line_num, count)?;
+                stack.push((line_num, Open::Repeat(count), vec![]));
+                continue;
+            }
+            ["repeat", ..] => return error(line_num, "expected `repeat <count> {`"),
+            ["macro", name, "{"] => {
+                if stack.len() > 1 {
+                    return error(line_num, "macros can only be defined at the top level");
+                }
+                let name = parse_name(line_num, Some(name), "macro")?;
+                stack.push((line_num, Open::Macro(name), vec![]));
+                continue;
+            }
+            ["macro", ..] => return error(line_num, "expected `macro <name> {`"),
+            [name] => Statement::Call(parse_name(line_num, Some(name), "macro")?),
+            [word, ..] => return error(line_num, format!("unexpected `{}`", word)),
+        };
+
+        stack.last_mut().unwrap().2.push((line_num, statement));
+    }
+
+    if stack.len() > 1 {
+        let (start, _, _) = stack.pop().unwrap();
+        return error(start, "block is never closed");
+    }
+
+    let (_, _, main) = stack.pop().unwrap();
+    Ok(Program { main, macros })
+}
+
+struct Interpreter<'a> {
+    macros: &'a HashMap<String, Block>,
+    variables: HashMap<String, i32>,
+    commands: Vec<Command>,
+    call_depth: usize,
+    steps: usize,
+}
+
+impl Interpreter<'_> {
+    fn eval(&self, line: usize, value: &Value) -> Result<i32, ScriptError> {
+        match value {
+            Value::Literal(num) => Ok(*num),
+            Value::Variable(name) => match self.variables.get(name) {
+                Some(num) => Ok(*num),
+                None => error(line, format!("undefined variable `{}`", name)),
+            },
+        }
+    }
+
+    fn step(&mut self, line: usize) -> Result<(), ScriptError> {
+        if self.steps == MAX_STEPS {
+            return error(
+                line,
+                format!("script runs for more than {} steps", MAX_STEPS),
+            );
+        }
+        self.steps += 1;
+        Ok(())
+    }
+
+    fn run(&mut self, block: &[(usize, Statement)]) -> Result<(), ScriptError> {
+        for (line, statement) in block {
+            let line = *line;
+            self.step(line)?;
+            match statement {
+                Statement::Move(dir, value) => {
+                    let dist = self.eval(line, value)?;
+                    if self.commands.len() == MAX_COMMANDS {
+                        return error(
+                            line,
+                            format!("script expands to more than {} commands", MAX_COMMANDS),
+                        );
+                    }
+                    self.commands.push(match dir {
+                        Direction::Forward => Command::Forward(dist),
+                        Direction::Up => Command::Up(dist),
+                        Direction::Down => Command::Down(dist),
+                    });
+                }
+                Statement::Let(name, value) => {
+                    let num = self.eval(line, value)?;
+                    self.variables.insert(name.clone(), num);
+                }
+                Statement::Repeat(count, body) => {
+                    let count = self.eval(line, count)?;
+                    if count < 0 {
+                        return error(line, format!("negative repeat count {}", count));
+                    }
+                    for _ in 0..count {
+                        self.step(line)?;
+                        self.run(body)?;
+                    }
+                }
+                Statement::Call(name) => {
+                    let Some(body) = self.macros.get(name) else {
+                        return error(line, format!("undefined macro `{}`", name));
+                    };
+                    if self.call_depth == MAX_CALL_DEPTH {
+                        return error(line, "macro calls are nested too deeply");
+                    }
+                    self.call_depth += 1;
+                    self.run(body)?;
+                    self.call_depth -= 1;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_compile() {
+    let source = "\
+# dive twice
+let d = 3
+macro dive {
+    down d
+    forward 2 # inline comment
+}
+repeat 2 {
+    dive
+}
+let d = 1
+dive
+up 4
+";
+    let commands = compile(source).unwrap();
+    assert_eq!(
+        commands,
+        vec![
+            Command::Down(3),
+            Command::Forward(2),
+            Command::Down(3),
+            Command::Forward(2),
+            Command::Down(1),
+            Command::Forward(2),
+            Command::Up(4),
+        ]
+    );
+
+    assert_eq!(compile("forward 1\nrepeat x {\n}\n").unwrap_err().line, 2);
+    assert_eq!(compile("repeat 2 {\nforward 1\n").unwrap_err().line, 1);
+    assert_eq!(compile("macro m {\nm\n}\nm\n").unwrap_err().line, 2);
+    assert_eq!(compile("forward\n").unwrap_err().line, 1);
+    let nested = "repeat 2000000000 {\nrepeat 2000000000 {\nforward 1\n}\n}\n";
+    assert_eq!(compile(nested).unwrap_err().line, 3);
+    // Loops without commands don't hit the command limit, but run for too long
+    let empty = "repeat 2000000000 {\nrepeat 2000000000 {\n}\n}\n";
+    assert_eq!(compile(empty).unwrap_err().line, 2);
+}
@@ -0,0 +1,96 @@
+use crate::number::{Number, Overflow};
+use crate::{Command, SubmarineModel};
+
+/// State of the submarine after a command was applied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step<N> {
+    pub command: Command,
+    pub horizontal: N,
+    pub depth: N,
+    pub aim: N,
+}
+
+/// The course of the submarine, one step per command.
+#[derive(Debug, Default)]
+pub struct Trajectory<N> {
+    pub steps: Vec<Step<N>>,
+}
+
+impl<N: Number> Trajectory<N> {
+    /// Feeds all commands to the model and records its state after every command.
+    pub fn record(
+        model: &mut dyn SubmarineModel<N>,
+        commands: &[Command],
+    ) -> Result<Self, Overflow> {
+        let steps = commands
+            .iter()
+            .map(|&command| {
+                model.apply(command)?;
+                let (horizontal, depth) = model.position();
+                Ok(Step {
+                    command,
+                    horizontal,
+                    depth,
+                    aim: model.aim(),
+                })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Trajectory { steps })
+    }
+
+    /// Returns the maximum depth together with the (zero based) step at which it was reached
+    /// first, or `None` for an empty trajectory.
+    pub fn max_depth(&self) -> Option<(usize, &N)> {
+        self.steps
+            .iter()
+            .enumerate()
+            // `max_by_key` returns the last maximum, but we want the first one
+            .rev()
+            .max_by_key(|(_, step)| &step.depth)
+            .map(|(idx, step)| (idx, &step.depth))
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("step,command,distance,horizontal,depth,aim\n");
+        for (idx, step) in self.steps.iter().enumerate() {
+            let (name, dist) = step.command.parts();
+            csv.push_str(&format!(
+                "{},{},{},{},{},{}\n",
+                idx, name, dist, step.horizontal, step.depth, step.aim
+            ));
+        }
+        csv
+    }
+
+    pub fn to_json(&self) -> String {
+        // All values are numbers or fixed command names, so nothing has to be escaped.
+        let steps: Vec<String> = self
+            .steps
+            .iter()
+            .enumerate()
+            .map(|(idx, step)| {
+                let (name, dist) = step.command.parts();
+                format!(
+                    "  {{\"step\": {}, \"command\": \"{}\", \"distance\": {}, \"horizontal\": {}, \"depth\": {}, \"aim\": {}}}",
+                    idx, name, dist, step.horizontal, step.depth, step.aim
+                )
+            })
+            .collect();
+
+        if steps.is_empty() {
+            String::from("[]\n")
+        } else {
+            format!("[\n{}\n]\n", steps.join(",\n"))
+        }
+    }
+}
+
+#[test]
+fn test_max_depth() {
+    let commands = crate::script::compile("down 5\nforward 2\nup 5\ndown 5\nup 1").unwrap();
+    let trajectory =
+        Trajectory::record(&mut crate::DirectModel::<i64>::default(), &commands).unwrap();
+    assert_eq!(trajectory.max_depth(), Some((0, &5)));
+    assert_eq!(Trajectory::<i64>::default().max_depth(), None);
+}
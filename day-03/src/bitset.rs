@@ -0,0 +1,100 @@
+/// Fixed size set of bits, packed into `u64` words.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitSet {
+    words: Vec<u64>,
+    len: usize,
+}
+
+impl BitSet {
+    /// Creates a set of `len` bits which are all zero.
+    pub fn zeros(len: usize) -> Self {
+        BitSet {
+            words: vec![0; len.div_ceil(64)],
+            len,
+        }
+    }
+
+    /// Creates a set of `len` bits which are all one.
+    pub fn ones(len: usize) -> Self {
+        let mut set = BitSet {
+            words: vec![u64::MAX; len.div_ceil(64)],
+            len,
+        };
+        // Bits past the end have to stay zero, so counting them works
+        if !len.is_multiple_of(64) {
+            *set.words.last_mut().unwrap() = (1 << (len % 64)) - 1;
+        }
+        set
+    }
+
+    pub fn get(&self, idx: usize) -> bool {
+        debug_assert!(idx < self.len);
+        self.words[idx / 64] & (1 << (idx % 64)) != 0
+    }
+
+    pub fn set(&mut self, idx: usize) {
+        debug_assert!(idx < self.len);
+        self.words[idx / 64] |= 1 << (idx % 64);
+    }
+
+    pub fn count_ones(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    /// Counts the bits that are set in both sets without building the intersection.
+    pub fn count_ones_and(&self, other: &BitSet) -> usize {
+        std::iter::zip(&self.words, &other.words)
+            .map(|(a, b)| (a & b).count_ones() as usize)
+            .sum()
+    }
+
+    /// Keeps only the bits that are also set in `other`.
+    pub fn and(&mut self, other: &BitSet) {
+        for (a, b) in std::iter::zip(&mut self.words, &other.words) {
+            *a &= b;
+        }
+    }
+
+    /// Keeps only the bits that are not set in `other`.
+    pub fn and_not(&mut self, other: &BitSet) {
+        for (a, b) in std::iter::zip(&mut self.words, &other.words) {
+            *a &= !b;
+        }
+    }
+
+    /// Iterates over the indices of all set bits in ascending order.
+    pub fn iter_ones(&self) -> impl Iterator<Item = usize> + '_ {
+        self.words.iter().enumerate().flat_map(|(word_idx, &word)| {
+            // Repeatedly take the lowest set bit and clear it
+            let mut rest = word;
+            std::iter::from_fn(move || {
+                if rest == 0 {
+                    return None;
+                }
+                let bit = rest.trailing_zeros() as usize;
+                rest &= rest - 1;
+                Some(64 * word_idx + bit)
+            })
+        })
+    }
+}
+
+#[test]
+fn test_bit_set() {
+    let mut a = BitSet::zeros(130);
+    a.set(0);
+    a.set(64);
+    a.set(129);
+    assert!(a.get(64) && !a.get(65));
+    assert_eq!(a.iter_ones().collect::<Vec<_>>(), vec![0, 64, 129]);
+
+    let ones = BitSet::ones(130);
+    assert_eq!(ones.count_ones(), 130);
+    assert_eq!(ones.count_ones_and(&a), 3);
+
+    let mut b = ones.clone();
+    b.and_not(&a);
+    assert_eq!(b.count_ones(), 127);
+    b.and(&a);
+    assert_eq!(b.count_ones(), 0);
+}
@@ -0,0 +1,154 @@
+use crate::bitset::BitSet;
+use crate::Diagnostic;
+
+/// Order in which the bit positions of the entries are looked at. Positions are counted from the
+/// most significant bit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ColumnOrder {
+    MsbFirst,
+    LsbFirst,
+    /// Only the given positions are looked at, in the given order.
+    Custom(Vec<usize>),
+}
+
+impl ColumnOrder {
+    fn positions(&self, width: usize) -> Vec<usize> {
+        match self {
+            ColumnOrder::MsbFirst => (0..width).collect(),
+            ColumnOrder::LsbFirst => (0..width).rev().collect(),
+            ColumnOrder::Custom(positions) => positions.clone(),
+        }
+    }
+}
+
+/// Bit criteria that filter the diagnostic entries. At every position of the column order, the
+/// candidates with a one are kept if `should_pick_ones` returns true for the amount of zeros and
+/// ones among the candidates, otherwise the candidates with a zero are kept.
+pub struct BitCriteria<F> {
+    should_pick_ones: F,
+    order: ColumnOrder,
+}
+
+/// A single filter step of the bit criteria.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilterStep {
+    pub position: usize,
+    pub num_zeros: usize,
+    pub num_ones: usize,
+    pub picked_ones: bool,
+    /// Number of candidates after the step
+    pub remaining: usize,
+}
+
+impl FilterStep {
+    /// Whether the candidates had as many zeros as ones, so the criteria had to break a tie.
+    pub fn is_tie(&self) -> bool {
+        self.num_zeros == self.num_ones
+    }
+}
+
+impl<F> BitCriteria<F>
+where
+    F: Fn(usize, usize) -> bool,
+{
+    /// Creates criteria that look at the bits starting at the most significant one.
+    pub fn new(should_pick_ones: F) -> Self {
+        BitCriteria {
+            should_pick_ones,
+            order: ColumnOrder::MsbFirst,
+        }
+    }
+
+    pub fn order(mut self, order: ColumnOrder) -> Self {
+        self.order = order;
+        self
+    }
+
+    /// Returns an iterator over the filter steps. The candidates can be inspected between the
+    /// steps.
+    pub fn steps<'a>(&'a self, diagnostic: &'a Diagnostic) -> FilterSteps<'a, F> {
+        let positions = self.order.positions(diagnostic.width);
+        assert!(
+            positions.iter().all(|&pos| pos < diagnostic.width),
+            "column order contains positions outside of the entries"
+        );
+
+        FilterSteps {
+            criteria: self,
+            diagnostic,
+            positions,
+            next: 0,
+            candidates: BitSet::ones(diagnostic.len),
+            num_candidates: diagnostic.len,
+        }
+    }
+}
+
+pub struct FilterSteps<'a, F> {
+    criteria: &'a BitCriteria<F>,
+    diagnostic: &'a Diagnostic,
+    positions: Vec<usize>,
+    next: usize,
+    candidates: BitSet,
+    num_candidates: usize,
+}
+
+impl<F> FilterSteps<'_, F>
+where
+    F: Fn(usize, usize) -> bool,
+{
+    /// Indices of the entries that are still candidates.
+    pub fn candidates(&self) -> impl Iterator<Item = usize> + '_ {
+        self.candidates.iter_ones()
+    }
+
+    /// Runs the remaining steps and returns the indices of the surviving entries.
+    pub fn into_survivors(mut self) -> Vec<usize> {
+        for _ in self.by_ref() {}
+        self.candidates().collect()
+    }
+}
+
+impl<F> Iterator for FilterSteps<'_, F>
+where
+    F: Fn(usize, usize) -> bool,
+{
+    type Item = FilterStep;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Filtering stops once a single candidate is left, like in the puzzle
+        if self.num_candidates <= 1 || self.next == self.positions.len() {
+            return None;
+        }
+
+        let position = self.positions[self.next];
+        self.next += 1;
+
+        let column = &self.diagnostic.columns[position];
+        let num_ones = self.candidates.count_ones_and(column);
+        let num_zeros = self.num_candidates - num_ones;
+
+        // When all candidates have the same bit, there is nothing to pick from
+        let picked_ones = match (num_zeros, num_ones) {
+            (_, 0) => false,
+            (0, _) => true,
+            _ => (self.criteria.should_pick_ones)(num_zeros, num_ones),
+        };
+
+        if picked_ones {
+            self.candidates.and(column);
+            self.num_candidates = num_ones;
+        } else {
+            self.candidates.and_not(column);
+            self.num_candidates = num_zeros;
+        }
+
+        Some(FilterStep {
+            position,
+            num_zeros,
+            num_ones,
+            picked_ones,
+            remaining: self.num_candidates,
+        })
+    }
+}
@@ -0,0 +1,167 @@
+/// The diagnostic entries in sorted order, comparing them from the most significant bit. Entries
+/// with the same prefix are a range of the sorted entries, and within that range the entries with
+/// a zero at the next bit come before the entries with a one. So the bit criteria can be evaluated
+/// by narrowing down a single range with binary searches, instead of filtering the candidates at
+/// every bit position.
+///
+/// The entries are packed into words, so the index needs no more memory than the entries do.
+pub struct SortedIndex {
+    /// Number of bits per entry
+    width: usize,
+    /// Number of entries
+    len: usize,
+    /// Number of words per entry
+    stride: usize,
+    /// The sorted entries. The first bit of an entry is the highest bit of its first word, so
+    /// comparing the words compares the bits.
+    words: Vec<u64>,
+}
+
+impl SortedIndex {
+    pub fn new<I>(width: usize, entries: impl Iterator<Item = I>) -> Self
+    where
+        I: Iterator<Item = bool>,
+    {
+        let stride = width.div_ceil(64);
+        let mut words = vec![];
+        let mut len = 0;
+        for bits in entries {
+            let start = words.len();
+            words.resize(start + stride, 0);
+            for (pos, bit) in bits.enumerate() {
+                words[start + pos / 64] |= (bit as u64) << (63 - pos % 64);
+            }
+            len += 1;
+        }
+
+        let mut entries: Vec<&[u64]> = words.chunks(stride.max(1)).collect();
+        entries.sort_unstable();
+        let words = entries.concat();
+
+        SortedIndex {
+            width,
+            len,
+            stride,
+            words,
+        }
+    }
+
+    fn bit(&self, entry: usize, pos: usize) -> bool {
+        (self.words[entry * self.stride + pos / 64] >> (63 - pos % 64)) & 1 == 1
+    }
+
+    /// Splits the range of entries that share the bits before `pos` into the entries with a zero
+    /// and the entries with a one at `pos`. Returns the first entry with a one.
+    fn split(&self, (mut lo, mut hi): (usize, usize), pos: usize) -> usize {
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if self.bit(mid, pos) {
+                hi = mid;
+            } else {
+                lo = mid + 1;
+            }
+        }
+        lo
+    }
+
+    /// Finds the entry that is left when filtering by the bit criteria, which are given by
+    /// `should_pick_ones` (see `get_rate`). When all candidates have the same bit, there is nothing
+    /// to pick from and that bit is taken as it is. This also covers a single candidate being left.
+    ///
+    /// Returns `None` when the index is empty.
+    pub fn find<F>(&self, should_pick_ones: F) -> Option<Vec<bool>>
+    where
+        F: Fn(usize, usize) -> bool,
+    {
+        let mut bits = Vec::with_capacity(self.width);
+        let (mut lo, mut hi) = (0, self.len);
+
+        while bits.len() < self.width {
+            let mid = self.split((lo, hi), bits.len());
+            let bit = match (mid - lo, hi - mid) {
+                (0, 0) => return None,
+                (_, 0) => false,
+                (0, _) => true,
+                (num_zeros, num_ones) => should_pick_ones(num_zeros, num_ones),
+            };
+
+            if bit {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+            bits.push(bit);
+        }
+
+        Some(bits)
+    }
+
+    /// Returns the bit positions along the path of `bits` where the candidates had as many zeros
+    /// as ones, so the bit criteria had to break a tie.
+    pub fn ties_along(&self, bits: &[bool]) -> Vec<usize> {
+        let mut ties = vec![];
+        let (mut lo, mut hi) = (0, self.len);
+        for (pos, &bit) in bits.iter().enumerate() {
+            if lo == hi {
+                break;
+            }
+            let mid = self.split((lo, hi), pos);
+            if mid - lo == hi - mid {
+                ties.push(pos);
+            }
+            if bit {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+        }
+        ties
+    }
+}
+
+#[test]
+fn test_find() {
+    let entries = [
+        "00100", "11110", "10110", "10111", "10101", "01111", "00111", "11100", "10000", "11001",
+        "00010", "01010",
+    ];
+    let index = SortedIndex::new(
+        5,
+        entries.iter().map(|entry| entry.chars().map(|c| c == '1')),
+    );
+
+    let to_string = |bits: Vec<bool>| -> String {
+        bits.into_iter()
+            .map(|bit| if bit { '1' } else { '0' })
+            .collect()
+    };
+    let oxygen = index.find(|zeros, ones| ones >= zeros).map(to_string);
+    assert_eq!(oxygen.as_deref(), Some("10111"));
+    let co2 = index.find(|zeros, ones| ones < zeros).map(to_string);
+    assert_eq!(co2.as_deref(), Some("01010"));
+    let co2_bits: Vec<bool> = "01010".chars().map(|c| c == '1').collect();
+    assert_eq!(index.ties_along(&co2_bits), vec![2]);
+    // The least common bit is never one that none of the candidates have
+    let duplicates = SortedIndex::new(
+        2,
+        [[true, true], [true, true]]
+            .into_iter()
+            .map(IntoIterator::into_iter),
+    );
+    assert_eq!(
+        duplicates.find(|zeros, ones| ones < zeros),
+        Some(vec![true, true])
+    );
+    assert_eq!(
+        SortedIndex::new(2, std::iter::empty::<std::iter::Empty<bool>>()).find(|_, _| true),
+        None
+    );
+
+    // Entries that span more than one word
+    let wide = |last: bool| (0..100).map(move |pos| pos == 0 || (pos == 99 && last));
+    let index = SortedIndex::new(100, [wide(true), wide(false), wide(true)].into_iter());
+    let oxygen = index.find(|zeros, ones| ones >= zeros);
+    assert_eq!(oxygen, Some(wide(true).collect()));
+    let co2 = index.find(|zeros, ones| ones < zeros);
+    assert_eq!(co2, Some(wide(false).collect()));
+}
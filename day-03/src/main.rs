@@ -1,55 +1,307 @@
-use std::io::{Read, Result};
+use std::fmt;
+use std::io::{Error, ErrorKind, Read, Result};
 
-fn read_input() -> Result<Vec<Vec<u8>>> {
+mod bitset;
+mod criteria;
+mod index;
+mod report;
+
+use bitset::BitSet;
+use criteria::{BitCriteria, ColumnOrder};
+use index::SortedIndex;
+use report::Report;
+
+/// The entries of the diagnostic report, stored column by column. `columns[pos]` holds the bit at
+/// position `pos` (counted from the most significant bit) of every entry, so counting the ones in
+/// a column is a popcount over packed words.
+struct Diagnostic {
+    /// Number of bits per entry
+    width: usize,
+    /// Number of entries
+    len: usize,
+    columns: Vec<BitSet>,
+}
+
+impl Diagnostic {
+    fn ones_in_column(&self, pos: usize) -> usize {
+        self.columns[pos].count_ones()
+    }
+
+    /// Iterates over the bits of an entry, starting at the most significant bit.
+    fn entry_bits(&self, entry: usize) -> impl Iterator<Item = bool> + '_ {
+        self.columns.iter().map(move |column| column.get(entry))
+    }
+
+    fn entry(&self, entry: usize) -> Rate {
+        Rate {
+            bits: self.entry_bits(entry).collect(),
+        }
+    }
+
+    fn index(&self) -> SortedIndex {
+        SortedIndex::new(
+            self.width,
+            (0..self.len).map(|entry| self.entry_bits(entry)),
+        )
+    }
+}
+
+/// Which bit counts as the most common one when a column has as many ones as zeros. The least
+/// common bit is always the other one, so both tasks resolve ties the same way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TieBreak {
+    Ones,
+    Zeros,
+}
+
+impl TieBreak {
+    fn most_common_is_one(self, num_zeros: usize, num_ones: usize) -> bool {
+        use std::cmp::Ordering;
+        match num_ones.cmp(&num_zeros) {
+            Ordering::Greater => true,
+            Ordering::Less => false,
+            Ordering::Equal => self.bit(),
+        }
+    }
+
+    /// The bit that counts as the most common one on a tie.
+    fn bit(self) -> bool {
+        self == TieBreak::Ones
+    }
+}
+
+/// A rate made up of the bits of the diagnostic, so it can have any width.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Rate {
+    /// Most significant bit first
+    bits: Vec<bool>,
+}
+
+impl Rate {
+    /// Returns `None` if the rate has more than 128 significant bits.
+    fn to_u128(&self) -> Option<u128> {
+        let significant_bits = self.bits.iter().skip_while(|bit| !**bit);
+        if significant_bits.clone().count() > 128 {
+            return None;
+        }
+        Some(significant_bits.fold(0, |acc, bit| (acc << 1) | *bit as u128))
+    }
+
+    /// Hexadecimal form of the rate, which works for any width
+    fn to_hex(&self) -> String {
+        // Pad at the front, so the bits can be grouped into nibbles from the most significant bit
+        let padding = (4 - self.bits.len() % 4) % 4;
+        let bits: Vec<bool> = std::iter::repeat_n(false, padding)
+            .chain(self.bits.iter().copied())
+            .collect();
+        let digits: String = bits
+            .chunks(4)
+            .map(|nibble| nibble.iter().fold(0, |acc, bit| (acc << 1) | *bit as u32))
+            .map(|nibble| char::from_digit(nibble, 16).unwrap())
+            .skip_while(|c| *c == '0')
+            .collect();
+
+        if digits.is_empty() {
+            String::from("0x0")
+        } else {
+            format!("0x{}", digits)
+        }
+    }
+
+    fn flipped(&self) -> Rate {
+        Rate {
+            bits: self.bits.iter().map(|bit| !bit).collect(),
+        }
+    }
+}
+
+/// Binary form of the rate, including leading zeros
+impl fmt::Display for Rate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for bit in &self.bits {
+            write!(f, "{}", if *bit { '1' } else { '0' })?;
+        }
+        Ok(())
+    }
+}
+
+/// Multiplies two rates, or returns `None` if the product doesn't fit into a `u128`.
+fn rate_product(a: &Rate, b: &Rate) -> Option<u128> {
+    a.to_u128()?.checked_mul(b.to_u128()?)
+}
+
+fn read_input() -> Result<Diagnostic> {
     let mut input_file = std::fs::File::open("input.txt")?;
     let mut input = String::new();
     input_file.read_to_string(&mut input)?;
+    parse_diagnostic(&input)
+}
 
-    let mut entries = vec![];
-    for line in input.lines() {
-        let bits: Vec<u8> = line
-            .chars()
-            .map(|c| c.to_digit(10).unwrap() as u8)
-            .collect();
-        entries.push(bits);
+fn parse_diagnostic(input: &str) -> Result<Diagnostic> {
+    let invalid_data = |message: String| Error::new(ErrorKind::InvalidData, message);
+
+    let width = match input.lines().next() {
+        Some(line) if !line.is_empty() => line.len(),
+        _ => return Err(invalid_data(String::from("no diagnostic entries"))),
+    };
+    let len = input.lines().count();
+    let mut columns = vec![BitSet::zeros(len); width];
+
+    for (entry, line) in input.lines().enumerate() {
+        if line.len() != width {
+            return Err(invalid_data(format!(
+                "line {}: expected {} bits, found {}",
+                entry + 1,
+                width,
+                line.len()
+            )));
+        }
+        for (pos, c) in line.chars().enumerate() {
+            match c {
+                '0' => {}
+                '1' => columns[pos].set(entry),
+                _ => {
+                    return Err(invalid_data(format!(
+                        "line {}: invalid bit {:?}",
+                        entry + 1,
+                        c
+                    )))
+                }
+            }
+        }
     }
 
-    Ok(entries)
+    Ok(Diagnostic {
+        width,
+        len,
+        columns,
+    })
+}
+
+fn gamma_rate(diagnostic: &Diagnostic, tie_break: TieBreak) -> Rate {
+    Rate {
+        bits: (0..diagnostic.width)
+            // Map count of ones to bits in gamma rate
+            .map(|pos| {
+                let num_ones = diagnostic.ones_in_column(pos);
+                tie_break.most_common_is_one(diagnostic.len - num_ones, num_ones)
+            })
+            .collect(),
+    }
 }
 
-fn task_1(entries: &Vec<Vec<u8>>) {
-    let mut ones: Vec<i32> = vec![0; entries[0].len()];
+fn task_1(diagnostic: &Diagnostic, tie_break: TieBreak) {
+    let gamma = gamma_rate(diagnostic, tie_break);
 
-    for entry in entries {
-        for (pos, bit) in entry.iter().enumerate() {
-            ones[pos] += *bit as i32;
-        }
+    // epsilon has all bits of gamma flipped. Computing it from the bits (instead of
+    // `(1 << width) - gamma - 1`) works for any width.
+    let epsilon = gamma.flipped();
+
+    print_product("Task 1", ("gamma", &gamma), ("epsilon", &epsilon));
+
+    let ties: Vec<usize> = (0..diagnostic.width)
+        .filter(|&pos| 2 * diagnostic.ones_in_column(pos) == diagnostic.len)
+        .collect();
+    print_ties("Task 1", &[("gamma", &ties, tie_break.bit())]);
+}
+
+fn task_2(diagnostic: &Diagnostic, tie_break: TieBreak, order: &ColumnOrder) {
+    let pick_most_common = |num_zeros, num_ones| tie_break.most_common_is_one(num_zeros, num_ones);
+    let pick_least_common =
+        |num_zeros, num_ones| !tie_break.most_common_is_one(num_zeros, num_ones);
+
+    // The index only works for the most significant bit first, which is the order of the puzzle
+    if *order == ColumnOrder::MsbFirst {
+        let index = diagnostic.index();
+        let oxygen_rate = get_rate(&index, pick_most_common);
+        let co2_rate = get_rate(&index, pick_least_common);
+
+        print_product("Task 2", ("oxygen", &oxygen_rate), ("co2", &co2_rate));
+
+        let oxygen_ties = index.ties_along(&oxygen_rate.bits);
+        let co2_ties = index.ties_along(&co2_rate.bits);
+        print_ties(
+            "Task 2",
+            &[
+                ("oxygen", &oxygen_ties, tie_break.bit()),
+                ("co2", &co2_ties, !tie_break.bit()),
+            ],
+        );
+        return;
     }
 
-    let num_half_entries = entries.len() as i32 / 2;
-    let gamma = ones
-        .iter()
-        // Map count of ones to bits in gamma rate
-        .map(|num_ones| if *num_ones > num_half_entries { 1 } else { 0 })
-        // Convert bits to int
-        .fold(0, |acc, bit| (acc << 1) + bit);
+    let oxygen_criteria = BitCriteria::new(pick_most_common).order(order.clone());
+    let co2_criteria = BitCriteria::new(pick_least_common).order(order.clone());
 
-    // epsilon has all bits (excepct leading zeros) of gamma flipped. When added, epsilon and
-    // gamma will produce a binary number with only ones and the same amount of bits as the input
-    // entries. The sum is one less than the binary number starting with a one, followed by the same
-    // amount of zeros (this number is `1 << ones.len()`).
-    let epsilon = (1 << ones.len()) - gamma - 1;
+    let (oxygen_survivors, oxygen_ties) = filter_with_ties(&oxygen_criteria, diagnostic);
+    let (co2_survivors, co2_ties) = filter_with_ties(&co2_criteria, diagnostic);
 
-    let result = gamma * epsilon;
-    println!("Task 1: {}", result);
+    match (oxygen_survivors.as_slice(), co2_survivors.as_slice()) {
+        ([oxygen], [co2]) => {
+            let oxygen_rate = diagnostic.entry(*oxygen);
+            let co2_rate = diagnostic.entry(*co2);
+            print_product("Task 2", ("oxygen", &oxygen_rate), ("co2", &co2_rate));
+        }
+        _ => println!(
+            "Task 2: the filters left {} oxygen and {} co2 entries instead of one each",
+            oxygen_survivors.len(),
+            co2_survivors.len(),
+        ),
+    }
+    print_ties(
+        "Task 2",
+        &[
+            ("oxygen", &oxygen_ties, tie_break.bit()),
+            ("co2", &co2_ties, !tie_break.bit()),
+        ],
+    );
 }
 
-fn task_2(entries: &Vec<Vec<u8>>) {
-    let oxygen_rate = get_rate(entries, |num_zeros, num_ones| num_ones >= num_zeros);
-    let co2_rate = get_rate(entries, |num_zeros, num_ones| num_ones < num_zeros);
+/// Returns the surviving entries and the bit positions where the criteria had to break a tie.
+fn filter_with_ties<F>(
+    criteria: &BitCriteria<F>,
+    diagnostic: &Diagnostic,
+) -> (Vec<usize>, Vec<usize>)
+where
+    F: Fn(usize, usize) -> bool,
+{
+    let mut steps = criteria.steps(diagnostic);
+    let ties = steps
+        .by_ref()
+        .filter(|step| step.is_tie())
+        .map(|step| step.position)
+        .collect();
+    (steps.into_survivors(), ties)
+}
 
-    let result = oxygen_rate * co2_rate;
-    println!("Task 2: {}", result);
+/// Prints the bit positions (starting at the most significant bit) where the tie break decided
+/// which bit to pick, and the bit that was kept at those positions. Nothing is printed when there
+/// were no ties.
+fn print_ties(task: &str, ties: &[(&str, &[usize], bool)]) {
+    for (name, positions, kept_bit) in ties {
+        if positions.is_empty() {
+            continue;
+        }
+        let positions: Vec<String> = positions.iter().map(|pos| pos.to_string()).collect();
+        println!(
+            "{}: {} had ties at bit positions {} (kept the {})",
+            task,
+            name,
+            positions.join(", "),
+            if *kept_bit { "ones" } else { "zeros" }
+        );
+    }
+}
+
+/// Prints the product of the rates, or the rates in binary if the product is too large.
+fn print_product(task: &str, (name_a, a): (&str, &Rate), (name_b, b): (&str, &Rate)) {
+    match rate_product(a, b) {
+        Some(result) => println!("{}: {}", task, result),
+        None => println!(
+            "{}: product doesn't fit into 128 bits ({} = {}, {} = {})",
+            task, name_a, a, name_b, b
+        ),
+    }
 }
 
 /// Calculates the rate of one entry. The entry is picked by filtering all entries until one is
@@ -63,43 +315,219 @@ fn task_2(entries: &Vec<Vec<u8>>) {
 /// `should_pick_ones` function. It gets passed the amount of zeros and ones and returns whether
 /// the candidates with ones should be kept, otherwise the candidates with zeros are kept. This way
 /// we can reuse the function to calculate both the oxygen and the co2 rate.
-fn get_rate<F>(entries: &Vec<Vec<u8>>, should_pick_ones: F) -> i32
+///
+/// Instead of filtering the candidates, we narrow down the range of the sorted entries that share
+/// the bits picked so far. Within that range, the entries with a zero at the current bit come
+/// before the entries with a one, so a binary search tells how many there are of each.
+fn get_rate<F>(index: &SortedIndex, should_pick_ones: F) -> Rate
 where
-    F: Fn(usize, usize) -> bool
+    F: Fn(usize, usize) -> bool,
 {
-    let mut candidates: Vec<usize> = (0..entries.len()).into_iter().collect();
+    let bits = index.find(should_pick_ones).expect("no diagnostic entries");
+    Rate { bits }
+}
+
+/// Same as `get_rate`, but filters the candidates at every bit position. The candidates are a bit
+/// set over all entries, so counting the ones among them and filtering them are bitwise operations
+/// with the column. Kept as a baseline for the benchmark.
+fn filter_rate<F>(diagnostic: &Diagnostic, should_pick_ones: F) -> Rate
+where
+    F: Fn(usize, usize) -> bool,
+{
+    let mut candidates = BitSet::ones(diagnostic.len);
+    let mut num_candidates = diagnostic.len;
     let mut offset = 0;
 
-    while candidates.len() > 1 && offset < entries[0].len() {
-        let mut zero_candidates = Vec::new();
-        let mut one_candidates = Vec::new();
+    while num_candidates > 1 && offset < diagnostic.width {
+        let column = &diagnostic.columns[offset];
+        let num_ones = candidates.count_ones_and(column);
+        let num_zeros = num_candidates - num_ones;
 
-        for idx in &candidates {
-            if entries[*idx][offset] == 0 {
-                zero_candidates.push(*idx);
-            } else {
-                one_candidates.push(*idx);
-            }
-        }
+        // When all candidates have the same bit, there is nothing to pick from
+        let picked_ones = match (num_zeros, num_ones) {
+            (_, 0) => false,
+            (0, _) => true,
+            _ => should_pick_ones(num_zeros, num_ones),
+        };
 
-        if should_pick_ones(zero_candidates.len(), one_candidates.len()) {
-            candidates = one_candidates;
+        if picked_ones {
+            candidates.and(column);
+            num_candidates = num_ones;
         } else {
-            candidates = zero_candidates;
+            candidates.and_not(column);
+            num_candidates = num_zeros;
         }
 
         offset += 1;
     }
 
-    assert_eq!(candidates.len(), 1, "filter didn't yield exactly one entry");
-    let entry = &entries[candidates[0]];
-    let rate: i32 = entry.iter().fold(0, |acc, bit| (acc << 1) + *bit as i32);
+    // Duplicate entries can be left after the last bit, and they all have the same bits
+    let entry = candidates
+        .iter_ones()
+        .next()
+        .expect("no diagnostic entries");
+    Rate {
+        bits: diagnostic.entry_bits(entry).collect(),
+    }
+}
+
+#[test]
+fn test_rate() {
+    let rate = |s: &str| Rate {
+        bits: s.chars().map(|c| c == '1').collect(),
+    };
+    assert_eq!(rate("0010110").to_u128(), Some(22));
+    assert_eq!(rate("0010110").flipped().to_string(), "1101001");
+    // Leading zeros don't count towards the 128 bits
+    let wide = format!("{}1{}", "0".repeat(100), "0".repeat(127));
+    assert_eq!(rate(&wide).to_u128(), Some(1 << 127));
+    assert_eq!(rate(&format!("1{}", wide)).to_u128(), None);
+    assert_eq!(rate_product(&rate(&wide), &rate("10")), None);
+    assert_eq!(rate("0010110").to_hex(), "0x16");
+    assert_eq!(rate(&wide).to_hex(), format!("0x8{}", "0".repeat(31)));
+    assert_eq!(rate("000").to_hex(), "0x0");
+}
+
+#[test]
+fn test_bit_criteria() {
+    let diagnostic = parse_diagnostic(
+        "00100\n11110\n10110\n10111\n10101\n01111\n00111\n11100\n10000\n11001\n00010\n01010\n",
+    )
+    .unwrap();
+    let oxygen = BitCriteria::new(|num_zeros, num_ones| num_ones >= num_zeros);
+
+    // Same result as the sorted index
+    let survivors = oxygen.steps(&diagnostic).into_survivors();
+    assert_eq!(survivors.len(), 1);
+    assert_eq!(
+        Some(diagnostic.entry(survivors[0]).bits),
+        diagnostic
+            .index()
+            .find(|num_zeros, num_ones| num_ones >= num_zeros)
+    );
+
+    let steps: Vec<_> = oxygen
+        .order(ColumnOrder::LsbFirst)
+        .steps(&diagnostic)
+        .map(|step| (step.position, step.remaining))
+        .collect();
+    assert_eq!(steps, vec![(4, 7), (3, 4), (2, 2), (1, 1)]);
+
+    // Only looking at the first bit leaves all entries starting with a one
+    let first_bit = BitCriteria::new(|_, _| true).order(ColumnOrder::Custom(vec![0]));
+    assert_eq!(first_bit.steps(&diagnostic).into_survivors().len(), 7);
+
+    // The least common bit is never one that none of the candidates have
+    let duplicates = parse_diagnostic("011\n011\n").unwrap();
+    let least_common = |num_zeros, num_ones| num_ones < num_zeros;
+    assert_eq!(
+        filter_rate(&duplicates, least_common),
+        get_rate(&duplicates.index(), least_common)
+    );
+}
+
+fn bench(diagnostic: &Diagnostic) {
+    use std::time::Instant;
+
+    const RUNS: u32 = 100;
+    let pick_most_common = |num_zeros, num_ones| num_ones >= num_zeros;
+    let pick_least_common = |num_zeros, num_ones| num_ones < num_zeros;
+
+    let start = Instant::now();
+    let mut expected = None;
+    for _ in 0..RUNS {
+        expected = Some((
+            filter_rate(diagnostic, pick_most_common),
+            filter_rate(diagnostic, pick_least_common),
+        ));
+    }
+    let filter_time = start.elapsed() / RUNS;
+
+    let start = Instant::now();
+    let mut index = diagnostic.index();
+    for _ in 1..RUNS {
+        index = diagnostic.index();
+    }
+    let build_time = start.elapsed() / RUNS;
 
-    rate
+    let start = Instant::now();
+    let mut rates = None;
+    for _ in 0..RUNS {
+        rates = Some((
+            get_rate(&index, pick_most_common),
+            get_rate(&index, pick_least_common),
+        ));
+    }
+    let index_time = start.elapsed() / RUNS;
+
+    assert_eq!(rates, expected);
+    println!("filtered bit sets: {:?} per run", filter_time);
+    println!(
+        "sorted index:      {:?} per run, after {:?} to build it",
+        index_time, build_time
+    );
 }
 
 fn main() {
-    let entries = read_input().expect("error reading input");
-    task_1(&entries);
-    task_2(&entries);
+    let diagnostic = read_input().expect("error reading input");
+
+    // `cargo run --release -- bench` compares the index with filtering the candidates
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args == ["bench"] {
+        bench(&diagnostic);
+        return;
+    }
+
+    // `cargo run -- [--tie-break ones|zeros] [--order msb|lsb|<positions>] [--report text|json]`
+    //
+    // By default ones win ties, like for the oxygen rating in the puzzle. The order is the order of
+    // the bit positions that the filters of task 2 look at, which can also be a comma separated
+    // list of positions (0 is the most significant bit). With `--report`, a full diagnostic report
+    // is printed instead of the answers.
+    let usage = || -> ! {
+        eprintln!(
+            "usage: day-03 [bench | [--tie-break ones|zeros] [--order msb|lsb|<positions>] \
+             [--report text|json]]"
+        );
+        std::process::exit(1);
+    };
+    let mut tie_break = TieBreak::Ones;
+    let mut order = ColumnOrder::MsbFirst;
+    let mut report_format = None;
+    let mut args = args.into_iter();
+    while let Some(flag) = args.next() {
+        match (flag.as_str(), args.next().as_deref()) {
+            ("--tie-break", Some("ones")) => tie_break = TieBreak::Ones,
+            ("--tie-break", Some("zeros")) => tie_break = TieBreak::Zeros,
+            ("--order", Some("msb")) => order = ColumnOrder::MsbFirst,
+            ("--order", Some("lsb")) => order = ColumnOrder::LsbFirst,
+            ("--report", Some(format @ ("text" | "json"))) => {
+                report_format = Some(format.to_owned())
+            }
+            ("--order", Some(positions)) => {
+                let positions: std::result::Result<Vec<usize>, _> =
+                    positions.split(',').map(str::parse).collect();
+                match positions {
+                    Ok(positions) if positions.iter().all(|&pos| pos < diagnostic.width) => {
+                        order = ColumnOrder::Custom(positions)
+                    }
+                    _ => usage(),
+                }
+            }
+            _ => usage(),
+        }
+    }
+
+    if let Some(format) = report_format {
+        let report = Report::new(&diagnostic, tie_break, &order);
+        if format == "json" {
+            print!("{}", report.to_json());
+        } else {
+            print!("{}", report.to_text());
+        }
+        return;
+    }
+
+    task_1(&diagnostic, tie_break);
+    task_2(&diagnostic, tie_break, &order);
 }
@@ -0,0 +1,297 @@
+use crate::criteria::{BitCriteria, ColumnOrder, FilterStep};
+use crate::{gamma_rate, rate_product, Diagnostic, Rate, TieBreak};
+
+/// Everything that goes into the answers of both tasks, so the answers can be checked by hand.
+pub struct Report {
+    /// Number of zeros and ones per bit position
+    columns: Vec<(usize, usize)>,
+    gamma: Rate,
+    epsilon: Rate,
+    oxygen: RatingTrace,
+    co2: RatingTrace,
+}
+
+/// How the candidates for a rating were eliminated.
+struct RatingTrace {
+    steps: Vec<FilterStep>,
+    /// Indices of the entries that are left after the last step
+    survivors: Vec<usize>,
+    /// Only set if exactly one entry survived
+    rate: Option<Rate>,
+}
+
+impl RatingTrace {
+    fn new<F>(criteria: BitCriteria<F>, diagnostic: &Diagnostic) -> Self
+    where
+        F: Fn(usize, usize) -> bool,
+    {
+        let mut steps_iter = criteria.steps(diagnostic);
+        let steps = steps_iter.by_ref().collect();
+        let survivors = steps_iter.into_survivors();
+        let rate = match survivors.as_slice() {
+            [entry] => Some(diagnostic.entry(*entry)),
+            _ => None,
+        };
+
+        RatingTrace {
+            steps,
+            survivors,
+            rate,
+        }
+    }
+}
+
+impl Report {
+    pub fn new(diagnostic: &Diagnostic, tie_break: TieBreak, order: &ColumnOrder) -> Self {
+        let columns = (0..diagnostic.width)
+            .map(|pos| {
+                let num_ones = diagnostic.ones_in_column(pos);
+                (diagnostic.len - num_ones, num_ones)
+            })
+            .collect();
+        let gamma = gamma_rate(diagnostic, tie_break);
+        let epsilon = gamma.flipped();
+
+        let oxygen = BitCriteria::new(|num_zeros, num_ones| {
+            tie_break.most_common_is_one(num_zeros, num_ones)
+        })
+        .order(order.clone());
+        let co2 = BitCriteria::new(|num_zeros, num_ones| {
+            !tie_break.most_common_is_one(num_zeros, num_ones)
+        })
+        .order(order.clone());
+
+        Report {
+            columns,
+            gamma,
+            epsilon,
+            oxygen: RatingTrace::new(oxygen, diagnostic),
+            co2: RatingTrace::new(co2, diagnostic),
+        }
+    }
+
+    fn power_consumption(&self) -> Option<u128> {
+        rate_product(&self.gamma, &self.epsilon)
+    }
+
+    fn life_support(&self) -> Option<u128> {
+        rate_product(self.oxygen.rate.as_ref()?, self.co2.rate.as_ref()?)
+    }
+
+    pub fn to_text(&self) -> String {
+        fn rate_text(rate: &Rate) -> String {
+            let decimal = match rate.to_u128() {
+                Some(decimal) => decimal.to_string(),
+                None => String::from("too large"),
+            };
+            format!("{} (decimal {}, hex {})", rate, decimal, rate.to_hex())
+        }
+
+        fn product_text(product: Option<u128>) -> String {
+            match product {
+                Some(product) => product.to_string(),
+                None => String::from("doesn't fit into 128 bits"),
+            }
+        }
+
+        fn trace_text(text: &mut String, name: &str, trace: &RatingTrace) {
+            text.push_str(&format!("{}:\n", name));
+            for step in &trace.steps {
+                text.push_str(&format!(
+                    "  bit {:>3}: {} zeros, {} ones -> kept {}s, {} left\n",
+                    step.position,
+                    step.num_zeros,
+                    step.num_ones,
+                    step.picked_ones as u8,
+                    step.remaining
+                ));
+            }
+            match &trace.rate {
+                Some(rate) => text.push_str(&format!("  rating: {}\n", rate_text(rate))),
+                None => text.push_str(&format!(
+                    "  rating: {} entries left instead of one\n",
+                    trace.survivors.len()
+                )),
+            }
+        }
+
+        let mut text = String::from("Columns:\n");
+        for (pos, (num_zeros, num_ones)) in self.columns.iter().enumerate() {
+            text.push_str(&format!(
+                "  bit {:>3}: {} zeros, {} ones\n",
+                pos, num_zeros, num_ones
+            ));
+        }
+        text.push_str(&format!("Gamma: {}\n", rate_text(&self.gamma)));
+        text.push_str(&format!("Epsilon: {}\n", rate_text(&self.epsilon)));
+        text.push_str(&format!(
+            "Power consumption: {}\n",
+            product_text(self.power_consumption())
+        ));
+        trace_text(&mut text, "Oxygen generator", &self.oxygen);
+        trace_text(&mut text, "CO2 scrubber", &self.co2);
+        text.push_str(&format!(
+            "Life support: {}\n",
+            product_text(self.life_support())
+        ));
+        text
+    }
+
+    pub fn to_json(&self) -> String {
+        // All strings are made of digits and letters, so nothing has to be escaped.
+        fn rate_json(rate: Option<&Rate>) -> String {
+            let Some(rate) = rate else {
+                return String::from("null");
+            };
+            format!(
+                "{{\"binary\": \"{}\", \"decimal\": {}, \"hex\": \"{}\"}}",
+                rate,
+                number_json(rate.to_u128()),
+                rate.to_hex()
+            )
+        }
+
+        fn number_json(num: Option<u128>) -> String {
+            match num {
+                Some(num) => num.to_string(),
+                None => String::from("null"),
+            }
+        }
+
+        fn trace_json(trace: &RatingTrace) -> String {
+            let steps: Vec<String> = trace
+                .steps
+                .iter()
+                .map(|step| {
+                    format!(
+                        "{{\"position\": {}, \"zeros\": {}, \"ones\": {}, \"kept\": {}, \"remaining\": {}}}",
+                        step.position,
+                        step.num_zeros,
+                        step.num_ones,
+                        step.picked_ones as u8,
+                        step.remaining
+                    )
+                })
+                .collect();
+            // Line numbers of the surviving entries
+            let survivors: Vec<String> = trace
+                .survivors
+                .iter()
+                .map(|entry| (entry + 1).to_string())
+                .collect();
+            format!(
+                "{{\"steps\": [{}], \"survivors\": [{}], \"rating\": {}}}",
+                steps.join(", "),
+                survivors.join(", "),
+                rate_json(trace.rate.as_ref())
+            )
+        }
+
+        let columns: Vec<String> = self
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(pos, (num_zeros, num_ones))| {
+                format!(
+                    "{{\"position\": {}, \"zeros\": {}, \"ones\": {}}}",
+                    pos, num_zeros, num_ones
+                )
+            })
+            .collect();
+
+        let fields = [
+            format!("\"columns\": [{}]", columns.join(", ")),
+            format!("\"gamma\": {}", rate_json(Some(&self.gamma))),
+            format!("\"epsilon\": {}", rate_json(Some(&self.epsilon))),
+            format!(
+                "\"power_consumption\": {}",
+                number_json(self.power_consumption())
+            ),
+            format!("\"oxygen\": {}", trace_json(&self.oxygen)),
+            format!("\"co2\": {}", trace_json(&self.co2)),
+            format!("\"life_support\": {}", number_json(self.life_support())),
+        ];
+        format!("{{\n  {}\n}}\n", fields.join(",\n  "))
+    }
+}
+
+#[test]
+fn test_report() {
+    let diagnostic = crate::parse_diagnostic(
+        "00100\n11110\n10110\n10111\n10101\n01111\n00111\n11100\n10000\n11001\n00010\n01010\n",
+    )
+    .unwrap();
+    let report = Report::new(&diagnostic, TieBreak::Ones, &ColumnOrder::MsbFirst);
+
+    assert_eq!(report.columns, vec![(5, 7), (7, 5), (4, 8), (5, 7), (7, 5)]);
+    let remaining = |trace: &RatingTrace| -> Vec<usize> {
+        trace.steps.iter().map(|step| step.remaining).collect()
+    };
+    assert_eq!(remaining(&report.oxygen), vec![7, 4, 3, 2, 1]);
+    assert_eq!(remaining(&report.co2), vec![5, 2, 1]);
+
+    let text = report.to_text();
+    assert!(text.contains("  bit   0: 5 zeros, 7 ones\n"));
+    assert!(text.contains("  bit   2: 1 zeros, 1 ones -> kept 0s, 1 left\n"));
+    assert!(text.contains("Power consumption: 198\n"));
+    assert!(text.contains("Life support: 230\n"));
+
+    fn skip_whitespace(json: &[u8], mut i: usize) -> usize {
+        while json.get(i).is_some_and(u8::is_ascii_whitespace) {
+            i += 1;
+        }
+        i
+    }
+    // Just enough of a JSON parser to tell whether the report is valid JSON. Returns the index
+    // after the value that starts at `i`.
+    fn parse_value(json: &[u8], i: usize) -> Option<usize> {
+        let i = skip_whitespace(json, i);
+        match json.get(i)? {
+            b'{' | b'[' => {
+                let (close, is_object) = match json[i] {
+                    b'{' => (b'}', true),
+                    _ => (b']', false),
+                };
+                let mut i = skip_whitespace(json, i + 1);
+                if json.get(i) == Some(&close) {
+                    return Some(i + 1);
+                }
+                loop {
+                    if is_object {
+                        i = skip_whitespace(json, parse_value(json, i)?);
+                        if json.get(i) != Some(&b':') {
+                            return None;
+                        }
+                        i += 1;
+                    }
+                    i = skip_whitespace(json, parse_value(json, i)?);
+                    match json.get(i)? {
+                        b',' => i += 1,
+                        c if *c == close => return Some(i + 1),
+                        _ => return None,
+                    }
+                }
+            }
+            b'"' => {
+                let len = json[i + 1..].iter().position(|&c| c == b'"')?;
+                Some(i + len + 2)
+            }
+            b'n' if json[i..].starts_with(b"null") => Some(i + 4),
+            b'-' | b'0'..=b'9' => {
+                let len = json[i + 1..]
+                    .iter()
+                    .take_while(|c| c.is_ascii_digit())
+                    .count();
+                Some(i + len + 1)
+            }
+            _ => None,
+        }
+    }
+
+    let json = report.to_json();
+    let end = parse_value(json.as_bytes(), 0).map(|end| skip_whitespace(json.as_bytes(), end));
+    assert_eq!(end, Some(json.len()));
+    assert!(json.contains("\"life_support\": 230"));
+    assert!(json.contains("\"rating\": {\"binary\": \"01010\", \"decimal\": 10, \"hex\": \"0xa\"}"));
+    assert_eq!(parse_value(b"{\"a\": [1, 2,]}", 0), None);
+}
@@ -0,0 +1,201 @@
+use std::collections::VecDeque;
+
+use crate::marker::Marker;
+use crate::rules::WinRule;
+use crate::Board;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    /// `turn` starts at 1 for the first drawn number
+    Draw {
+        turn: usize,
+        num: u8,
+    },
+    Win(Winner),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Winner {
+    pub board: usize,
+    pub turn: usize,
+    pub score: i32,
+}
+
+/// A game of bingo, which is played once while iterating over its events. Every drawn number is
+/// followed by the boards that won with it, in the order of the boards. The game ends when every
+/// board has won or all numbers are drawn.
+pub struct BingoGame<'a> {
+    nums: &'a [u8],
+    marker: Marker<'a>,
+    turn: usize,
+    boards_left: usize,
+    pending: VecDeque<Event>,
+}
+
+impl<'a> BingoGame<'a> {
+    pub fn new(nums: &'a [u8], boards: &'a [Board], rules: &[WinRule]) -> Self {
+        BingoGame {
+            nums,
+            marker: Marker::new(boards, rules),
+            turn: 0,
+            boards_left: boards.len(),
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Plays the whole game and collects the winners.
+    pub fn play(nums: &'a [u8], boards: &'a [Board], rules: &[WinRule]) -> GameResult {
+        let winners: Vec<Winner> = BingoGame::new(nums, boards, rules)
+            .filter_map(|event| match event {
+                Event::Win(winner) => Some(winner),
+                Event::Draw { .. } => None,
+            })
+            .collect();
+
+        let mut has_won = vec![false; boards.len()];
+        for winner in &winners {
+            has_won[winner.board] = true;
+        }
+        let never_won = (0..boards.len()).filter(|&b| !has_won[b]).collect();
+
+        GameResult { winners, never_won }
+    }
+}
+
+impl Iterator for BingoGame<'_> {
+    type Item = Event;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(event) = self.pending.pop_front() {
+            return Some(event);
+        }
+
+        if self.boards_left == 0 || self.turn == self.nums.len() {
+            return None;
+        }
+
+        let num = self.nums[self.turn];
+        self.turn += 1;
+
+        for board in self.marker.draw(num) {
+            self.boards_left -= 1;
+            self.pending.push_back(Event::Win(Winner {
+                board,
+                turn: self.turn,
+                score: self.marker.unmarked_sum(board) * num as i32,
+            }));
+        }
+
+        Some(Event::Draw {
+            turn: self.turn,
+            num,
+        })
+    }
+}
+
+pub struct GameResult {
+    /// In the order in which the boards won
+    pub winners: Vec<Winner>,
+    /// Boards that didn't win before the numbers ran out
+    pub never_won: Vec<usize>,
+}
+
+/// Which board won first or last.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Outcome<'a> {
+    Winner(&'a Winner),
+    /// Several boards won with the same draw
+    Simultaneous(&'a [Winner]),
+    NoWinner,
+    /// Only for the last winner: these boards never won, so there is no last winner
+    NeverWon(&'a [usize]),
+}
+
+impl GameResult {
+    /// The board that won first
+    pub fn first(&self) -> Outcome<'_> {
+        match self.winners.first() {
+            Some(first) => self.outcome_of_turn(first.turn),
+            None => Outcome::NoWinner,
+        }
+    }
+
+    /// The board that won last, provided that every board won
+    pub fn last(&self) -> Outcome<'_> {
+        match self.winners.last() {
+            None => Outcome::NoWinner,
+            Some(_) if !self.never_won.is_empty() => Outcome::NeverWon(&self.never_won),
+            Some(last) => self.outcome_of_turn(last.turn),
+        }
+    }
+
+    fn outcome_of_turn(&self, turn: usize) -> Outcome<'_> {
+        // The winners are ordered by turn, so the winners of a turn are next to each other
+        let start = self.winners.partition_point(|w| w.turn < turn);
+        let end = self.winners.partition_point(|w| w.turn <= turn);
+        match &self.winners[start..end] {
+            [winner] => Outcome::Winner(winner),
+            winners => Outcome::Simultaneous(winners),
+        }
+    }
+
+    /// Returns the `k`-th winner, starting at 0 for the first one.
+    pub fn kth_winner(&self, k: usize) -> Option<&Winner> {
+        self.winners.get(k)
+    }
+
+    /// Scores of all boards that won, in the order in which they won.
+    pub fn scores(&self) -> impl Iterator<Item = i32> + '_ {
+        self.winners.iter().map(|winner| winner.score)
+    }
+}
+
+#[test]
+fn test_bingo_game() {
+    use crate::rules::DEFAULT_RULES;
+
+    let boards = [
+        "1 2\n3 4".parse().unwrap(),
+        "5 6\n7 8".parse().unwrap(),
+        "1 9\n9 1".parse().unwrap(),
+    ];
+    let nums = [5, 1, 2, 7];
+
+    let events: Vec<Event> = BingoGame::new(&nums, &boards, &DEFAULT_RULES).collect();
+    assert_eq!(events.len(), 6);
+    assert_eq!(events[0], Event::Draw { turn: 1, num: 5 });
+    assert_eq!(
+        events[3],
+        Event::Win(Winner {
+            board: 0,
+            turn: 3,
+            score: (3 + 4) * 2
+        })
+    );
+
+    let result = BingoGame::play(&nums, &boards, &DEFAULT_RULES);
+    assert_eq!(result.scores().collect::<Vec<_>>(), vec![14, (6 + 8) * 7]);
+    assert_eq!(result.kth_winner(1).map(|w| w.board), Some(1));
+    assert_eq!(result.kth_winner(2), None);
+    assert_eq!(result.never_won, vec![2]);
+    assert_eq!(result.first(), Outcome::Winner(&result.winners[0]));
+    assert_eq!(result.last(), Outcome::NeverWon(&[2]));
+
+    // The third board wins with a diagonal
+    let result = BingoGame::play(&nums, &boards, &[WinRule::Diagonals]);
+    assert_eq!(
+        result.kth_winner(0).map(|w| (w.board, w.turn)),
+        Some((2, 2))
+    );
+    assert_eq!(result.never_won, vec![0, 1]);
+
+    assert_eq!(
+        BingoGame::play(&[], &boards, &DEFAULT_RULES).first(),
+        Outcome::NoWinner
+    );
+    // Both boards complete their first column with the 3
+    let boards = ["1 2\n3 4".parse().unwrap(), "1 5\n3 6".parse().unwrap()];
+    let result = BingoGame::play(&[1, 3], &boards, &DEFAULT_RULES);
+    assert_eq!(result.first(), Outcome::Simultaneous(&result.winners));
+    assert_eq!(result.last(), Outcome::Simultaneous(&result.winners));
+}
@@ -1,62 +1,94 @@
 use std::collections::HashSet;
-use std::io::{Read, Result};
+use std::io::{Error, ErrorKind, Read, Result};
+use std::str::FromStr;
 
+mod game;
+mod marker;
+mod mask;
+mod optimizer;
+mod rules;
+mod viz;
+
+use game::{BingoGame, GameResult, Outcome};
+use mask::Mask;
+use optimizer::{Goal, Plan};
+use rules::{WinRule, DEFAULT_RULES};
+
+/// A bingo board of any size. The size is inferred from the input block: every line is a row.
 struct Board {
+    /// Tiles row by row
     tiles: Vec<u8>,
+    width: usize,
+    height: usize,
 }
 
-// Not FromStr, because the input is expected to be correct, thus there is no need to return a
-// Result type
-impl From<&str> for Board {
-    fn from(input: &str) -> Self {
-        let mut tiles = Vec::with_capacity(25);
+impl FromStr for Board {
+    type Err = String;
+
+    fn from_str(input: &str) -> std::result::Result<Self, Self::Err> {
+        let mut tiles = vec![];
+        let mut width = 0;
+        let mut height = 0;
 
         for line in input.lines() {
-            let nums = line.split_whitespace().map(|s| s.parse::<u8>().unwrap());
+            let nums = line
+                .split_whitespace()
+                .map(|s| s.parse::<u8>())
+                .collect::<std::result::Result<Vec<u8>, _>>()
+                .map_err(|err| format!("invalid tile in row {}: {}", height + 1, err))?;
+            if height == 0 {
+                width = nums.len();
+            }
+            if nums.len() != width {
+                return Err(format!(
+                    "row {} has {} tiles, but the first row has {}",
+                    height + 1,
+                    nums.len(),
+                    width
+                ));
+            }
             tiles.extend(nums);
+            height += 1;
         }
 
-        assert_eq!(tiles.len(), 25);
+        if tiles.is_empty() {
+            return Err(String::from("empty board"));
+        }
 
-        Board { tiles }
+        Ok(Board {
+            tiles,
+            width,
+            height,
+        })
     }
 }
 
 impl Board {
-    fn is_won(&self, drawn_numbers: &HashSet<u8>) -> bool {
-        // row-wise
-        for row in 0..5 {
-            // Tiles of a row lie next to each other in memory. We can take a slice of the row and
-            // iterate over it
-            if self.tiles[(5 * row)..(5 * row + 5)]
-                .iter()
-                .all(|t| drawn_numbers.contains(t))
-            {
-                return true;
-            }
-        }
+    /// Masks of all tile combinations that make the board win with the given rules.
+    fn win_masks(&self, rules: &[WinRule]) -> Vec<Mask> {
+        rules
+            .iter()
+            .flat_map(|rule| rule.masks(self.width, self.height))
+            .collect()
+    }
 
-        // col-wise
-        for col in 0..5 {
-            if (0..5)
-                // Map rows of the column to the numbers in the column
-                .map(|row| self.tiles[5 * row + col])
-                .all(|t| drawn_numbers.contains(&t))
-            {
-                return true;
-            }
-        }
+    fn is_won(&self, drawn_numbers: &HashSet<u8>, rules: &[WinRule]) -> bool {
+        self.winning_mask(drawn_numbers, rules).is_some()
+    }
 
-        // Same in a bit shorter form
-        // if (0..5).any(|col| {
-        //     (0..5)
-        //         .map(|row| self.tiles[5 * row + col])
-        //         .all(|t| drawn_numbers.contains(&t))
-        // }) {
-        //     return true;
-        // }
+    /// The first win mask whose tiles have all been drawn.
+    fn winning_mask(&self, drawn_numbers: &HashSet<u8>, rules: &[WinRule]) -> Option<Mask> {
+        let marks: Mask = self
+            .tiles
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| drawn_numbers.contains(t))
+            .map(|(tile, _)| tile)
+            .collect();
 
-        false
+        self.win_masks(rules)
+            .into_iter()
+            .find(|mask| mask.is_subset(&marks))
     }
 
     fn unmarked_sum(&self, drawn_numbers: &HashSet<u8>) -> i32 {
@@ -67,14 +99,57 @@ impl Board {
     }
 }
 
+#[test]
+fn test_board_sizes() {
+    let board: Board = "1 2 3\n4 5 6\n7 8 9".parse().unwrap();
+    assert_eq!((board.width, board.height), (3, 3));
+    assert!(!board.is_won(&HashSet::from([1, 5, 9]), &DEFAULT_RULES));
+    assert!(board.is_won(&HashSet::from([2, 5, 8]), &DEFAULT_RULES));
+    assert!(board.is_won(&HashSet::from([1, 5, 9]), &[WinRule::Diagonals]));
+    assert!(!board.is_won(&HashSet::from([1, 3, 7]), &[WinRule::FourCorners]));
+
+    let board: Board = "1 2 3 4\n5 6 7 8".parse().unwrap();
+    assert_eq!((board.width, board.height), (4, 2));
+    assert!(board.is_won(&HashSet::from([4, 8]), &DEFAULT_RULES));
+    assert!(board.is_won(&HashSet::from([5, 6, 7, 8]), &DEFAULT_RULES));
+
+    // More tiles than fit into a single word of a mask
+    let rows: Vec<String> = (0..12)
+        .map(|row| {
+            let nums: Vec<String> = (0..12).map(|col| (12 * row + col).to_string()).collect();
+            nums.join(" ")
+        })
+        .collect();
+    let board: Board = rows.join("\n").parse().unwrap();
+    assert!(board.is_won(&(132..144).collect(), &DEFAULT_RULES));
+    assert!(!board.is_won(&(131..143).collect(), &DEFAULT_RULES));
+
+    assert!("1 2 3\n4 5".parse::<Board>().is_err());
+    assert!("1 2\n3 x".parse::<Board>().is_err());
+}
+
 fn read_input() -> Result<(Vec<u8>, Vec<Board>)> {
     let mut input_file = std::fs::File::open("input.txt")?;
     let mut input = String::new();
     input_file.read_to_string(&mut input)?;
 
+    let invalid_data = |message: String| Error::new(ErrorKind::InvalidData, message);
+
     let blocks: Vec<&str> = input.split("\n\n").collect();
-    let nums: Vec<u8> = blocks[0].split(',').map(|s| s.parse().unwrap()).collect();
-    let boards: Vec<Board> = blocks[1..].iter().map(|&b| b.into()).collect();
+    let nums = blocks[0]
+        .trim_end()
+        .split(',')
+        .map(|s| s.parse())
+        .collect::<std::result::Result<Vec<u8>, _>>()
+        .map_err(|err| invalid_data(format!("invalid drawn number: {}", err)))?;
+    let boards = blocks[1..]
+        .iter()
+        .enumerate()
+        .map(|(idx, b)| {
+            b.parse()
+                .map_err(|err| invalid_data(format!("board {}: {}", idx + 1, err)))
+        })
+        .collect::<Result<Vec<Board>>>()?;
 
     // Could even be written without collecting the blocks into a vector to avoid heap allocation,
     // even though we only store string slices which are references.
@@ -85,31 +160,50 @@ fn read_input() -> Result<(Vec<u8>, Vec<Board>)> {
     //     .split(',')
     //     .map(|s| s.parse().unwrap())
     //     .collect();
-    // let boards: Vec<Board> = blocks_iter.map(|b| b.into()).collect();
+    // let boards: Vec<Board> = blocks_iter.map(|b| b.parse().unwrap()).collect();
 
     Ok((nums, boards))
 }
 
-fn task_1(mut nums: Vec<u8>, boards: &Vec<Board>) {
-    // So we can pop from the end with O(1); popping from the front is O(n)
-    nums.reverse();
-    let mut drawn_numbers = HashSet::<u8>::with_capacity(nums.len());
+fn task_1(result: &GameResult) {
+    print_outcome("Task 1", result.first());
+}
+
+fn task_2(result: &GameResult) {
+    print_outcome("Task 2", result.last());
+}
 
-    let (winner_board, last_num) = loop {
-        // Unwrapping, because game has to be won when all numbers were drawn.
-        let num = nums.pop().unwrap();
-        drawn_numbers.insert(num);
+/// Boards are numbered from 1 in the output
+fn board_list(boards: impl Iterator<Item = usize>) -> String {
+    let boards: Vec<String> = boards.map(|board| (board + 1).to_string()).collect();
+    boards.join(", ")
+}
 
-        if let Some(board) = boards.iter().find(|b| b.is_won(&drawn_numbers)) {
-            break (board, num);
+fn print_outcome(task: &str, outcome: Outcome) {
+    match outcome {
+        Outcome::Winner(winner) => println!("{}: {}", task, winner.score),
+        Outcome::Simultaneous(winners) => {
+            let scores: Vec<String> = winners.iter().map(|w| w.score.to_string()).collect();
+            println!(
+                "{}: boards {} win at the same time with scores {}",
+                task,
+                board_list(winners.iter().map(|w| w.board)),
+                scores.join(", ")
+            );
         }
-    };
-
-    let result = winner_board.unmarked_sum(&drawn_numbers) * last_num as i32;
-    println!("Task 1: {}", result);
+        Outcome::NoWinner => println!("{}: no board wins", task),
+        Outcome::NeverWon(boards) => println!(
+            "{}: there is no last winner, boards {} never win",
+            task,
+            board_list(boards.iter().copied())
+        ),
+    }
 }
 
-fn task_2(mut nums: Vec<u8>, boards: &Vec<Board>) {
+/// Score of the board that wins last, found by checking every board against the set of drawn
+/// numbers after every draw. Kept as a baseline for the benchmark. Returns `None` if the numbers
+/// run out before every board has won.
+fn last_winner_score_hash_set(mut nums: Vec<u8>, boards: &[Board]) -> Option<i32> {
     // Popping from end with O(1)
     nums.reverse();
 
@@ -117,28 +211,172 @@ fn task_2(mut nums: Vec<u8>, boards: &Vec<Board>) {
     let mut boards: Vec<&Board> = boards.iter().collect();
     let mut drawn_numbers = HashSet::<u8>::with_capacity(nums.len());
 
-    let mut last_num = *nums.last().unwrap();
-    let mut last_winning_board = boards[0];
+    let mut last_num = *nums.last()?;
+    let mut last_winning_board = *boards.first()?;
 
     while !boards.is_empty() {
         // Assuming that there is only one board left at the end
         last_winning_board = boards[0];
-        // Assuming that every board has to be won when all numbers were drawn
-        last_num = nums.pop().unwrap();
+        last_num = nums.pop()?;
         drawn_numbers.insert(last_num);
 
-        boards = boards
-            .into_iter()
-            .filter(|b| !b.is_won(&drawn_numbers))
-            .collect();
+        boards.retain(|b| !b.is_won(&drawn_numbers, &DEFAULT_RULES));
+    }
+
+    Some(last_winning_board.unmarked_sum(&drawn_numbers) * last_num as i32)
+}
+
+/// Compares the bit mask marking to checking every board with a `HashSet` after every draw, by
+/// playing the game until the last board wins.
+fn bench(nums: &[u8], boards: &[Board]) {
+    use std::time::Instant;
+
+    const RUNS: u32 = 100;
+
+    let start = Instant::now();
+    let mut expected = None;
+    for _ in 0..RUNS {
+        expected = last_winner_score_hash_set(nums.to_vec(), boards);
     }
+    let hash_set_time = start.elapsed() / RUNS;
 
-    let result = last_winning_board.unmarked_sum(&drawn_numbers) * last_num as i32;
-    println!("Task 2: {}", result);
+    let start = Instant::now();
+    let mut score = None;
+    for _ in 0..RUNS {
+        let result = BingoGame::play(nums, boards, &DEFAULT_RULES);
+        score = match result.last() {
+            Outcome::Winner(winner) => Some(winner.score),
+            _ => None,
+        };
+    }
+    let marker_time = start.elapsed() / RUNS;
+
+    // The baseline can't tell simultaneous winners apart
+    if score != expected {
+        println!("The results differ: {:?} and {:?}", expected, score);
+    }
+
+    println!("HashSet:   {:?} per game", hash_set_time);
+    println!("Bit masks: {:?} per game", marker_time);
 }
 
 fn main() {
     let (nums, boards) = read_input().expect("error reading input");
-    task_1(nums.clone(), &boards);
-    task_2(nums, &boards);
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    // `--rules <rules>` picks the win rules of the game (see `parse_rules`), for example
+    // `--rules rows,columns,diagonals`. By default rows and columns win.
+    let mut rules = DEFAULT_RULES.to_vec();
+    if let Some(idx) = args.iter().position(|&arg| arg == "--rules") {
+        match args.get(idx + 1).and_then(|r| rules::parse_rules(r)) {
+            Some(parsed) => rules = parsed,
+            None => {
+                eprintln!(
+                    "invalid rules, expected a comma separated list of rows, columns, \
+                     diagonals, corners, blackout or pattern:<mask>"
+                );
+                std::process::exit(1);
+            }
+        }
+        args.drain(idx..idx + 2);
+    }
+
+    // `cargo run --release -- bench` compares the marking strategies
+    if args == ["bench"] {
+        bench(&nums, &boards);
+        return;
+    }
+
+    // `cargo run -- viz [--auto <ms>]` replays the game in the terminal, either one draw per
+    // press of enter or automatically with the given delay between draws
+    match args.as_slice() {
+        ["viz"] => return viz::replay(&nums, &boards, &rules, None).expect("error replaying"),
+        ["viz", "--auto", ms] => {
+            if let Ok(ms) = ms.parse() {
+                let delay = std::time::Duration::from_millis(ms);
+                return viz::replay(&nums, &boards, &rules, Some(delay)).expect("error replaying");
+            }
+        }
+        _ => {}
+    }
+
+    // The game is only played once, all answers come from its result
+    let result = BingoGame::play(&nums, &boards, &rules);
+
+    match args.as_slice() {
+        [] => {
+            task_1(&result);
+            task_2(&result);
+        }
+        // `cargo run -- winner <k>` prints the k-th winner, starting at 1
+        ["winner", k] => {
+            let winner = k
+                .parse::<usize>()
+                .ok()
+                .and_then(|k| result.kth_winner(k.checked_sub(1)?));
+            match winner {
+                Some(winner) => println!(
+                    "Board {} wins at turn {} with score {}",
+                    winner.board + 1,
+                    winner.turn,
+                    winner.score
+                ),
+                None => println!("There is no winner {}", k),
+            }
+        }
+        // `cargo run -- scores` prints the scores of all winners in the order in which they won
+        ["scores"] => {
+            for score in result.scores() {
+                println!("{}", score);
+            }
+        }
+        // `cargo run -- never-won` prints the boards that never win
+        ["never-won"] => {
+            for board in &result.never_won {
+                println!("Board {}", board + 1);
+            }
+        }
+        // `cargo run -- optimize <board> first|last` finds the shortest draw order that makes the
+        // board win first or last, ignoring the numbers of the input. Winning last is only fast for
+        // a few boards, see `optimizer::LastSearch`.
+        ["optimize", board, goal] => {
+            let goal = match *goal {
+                "first" => Some(Goal::First),
+                "last" => Some(Goal::Last),
+                _ => None,
+            };
+            let board = board
+                .parse::<usize>()
+                .ok()
+                .and_then(|board| board.checked_sub(1))
+                .filter(|&board| board < boards.len());
+            let (Some(board), Some(goal)) = (board, goal) else {
+                eprintln!(
+                    "expected a board between 1 and {} and first or last",
+                    boards.len()
+                );
+                std::process::exit(1);
+            };
+
+            match optimizer::shortest_order(&boards, &rules, board, goal) {
+                Plan::Order(order) => {
+                    let order: Vec<String> = order.iter().map(u8::to_string).collect();
+                    println!("{} numbers: {}", order.len(), order.join(","));
+                }
+                Plan::Impossible => println!(
+                    "Another board always wins no later than board {}",
+                    board + 1
+                ),
+            }
+        }
+        _ => {
+            eprintln!(
+                "usage: day-04 [--rules <rules>] [bench | winner <k> | scores | never-won | \
+                 viz [--auto <ms>] | optimize <board> first|last]"
+            );
+            std::process::exit(1);
+        }
+    }
 }
@@ -0,0 +1,102 @@
+use crate::rules::WinRule;
+use crate::{Board, Mask};
+
+/// Marks the drawn numbers on all boards using a bit mask per board. Every number knows on which
+/// tiles of which boards it is, so a draw only touches the boards that contain the number, and
+/// checking for a win is a comparison with the win masks that contain the marked tile.
+pub struct Marker<'a> {
+    boards: &'a [Board],
+    marks: Vec<Mask>,
+    won: Vec<bool>,
+    win_masks: Vec<Vec<Mask>>,
+    /// For every board and tile, the indices of the win masks of the board that contain the tile
+    tile_masks: Vec<Vec<Vec<usize>>>,
+    /// The (board, tile) pairs of every number
+    index: Vec<Vec<(usize, usize)>>,
+}
+
+impl<'a> Marker<'a> {
+    pub fn new(boards: &'a [Board], rules: &[WinRule]) -> Self {
+        let mut index = vec![vec![]; u8::MAX as usize + 1];
+        let mut win_masks = Vec::with_capacity(boards.len());
+        let mut tile_masks = Vec::with_capacity(boards.len());
+
+        for (board_idx, board) in boards.iter().enumerate() {
+            for (tile, &num) in board.tiles.iter().enumerate() {
+                index[num as usize].push((board_idx, tile));
+            }
+
+            let board_masks = board.win_masks(rules);
+            let masks_per_tile = (0..board.tiles.len())
+                .map(|tile| {
+                    (0..board_masks.len())
+                        .filter(|&mask_idx| board_masks[mask_idx].contains(tile))
+                        .collect()
+                })
+                .collect();
+            win_masks.push(board_masks);
+            tile_masks.push(masks_per_tile);
+        }
+
+        Marker {
+            boards,
+            marks: vec![Mask::default(); boards.len()],
+            won: vec![false; boards.len()],
+            win_masks,
+            tile_masks,
+            index,
+        }
+    }
+
+    /// Marks the number on all boards and returns the boards that won with this draw, in the
+    /// order of the boards.
+    pub fn draw(&mut self, num: u8) -> Vec<usize> {
+        let mut winners = vec![];
+
+        for &(board_idx, tile) in &self.index[num as usize] {
+            let marks = &mut self.marks[board_idx];
+            marks.set(tile);
+
+            if self.won[board_idx] {
+                continue;
+            }
+
+            // Only masks with the marked tile can have become complete
+            if self.tile_masks[board_idx][tile]
+                .iter()
+                .any(|&mask_idx| self.win_masks[board_idx][mask_idx].is_subset(marks))
+            {
+                self.won[board_idx] = true;
+                winners.push(board_idx);
+            }
+        }
+
+        winners
+    }
+
+    pub fn unmarked_sum(&self, board_idx: usize) -> i32 {
+        self.boards[board_idx]
+            .tiles
+            .iter()
+            .enumerate()
+            .filter(|(tile, _)| !self.marks[board_idx].contains(*tile))
+            .fold(0, |acc, (_, t)| acc + *t as i32)
+    }
+}
+
+#[test]
+fn test_marker() {
+    let boards: [Board; 2] = [
+        "1 2 3\n4 5 6\n7 8 9".parse().unwrap(),
+        "9 8\n7 1".parse().unwrap(),
+    ];
+    let mut marker = Marker::new(&boards, &crate::rules::DEFAULT_RULES);
+    assert_eq!(marker.draw(2), vec![]);
+    assert_eq!(marker.draw(8), vec![]);
+    assert_eq!(marker.draw(9), vec![1]);
+    assert_eq!(marker.draw(5), vec![0]);
+    // Boards only win once
+    assert_eq!(marker.draw(7), vec![]);
+    assert_eq!(marker.unmarked_sum(0), 1 + 3 + 4 + 6);
+    assert_eq!(marker.unmarked_sum(1), 1);
+}
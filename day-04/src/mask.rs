@@ -0,0 +1,108 @@
+/// A set of tiles of a board, where bit `i` is tile `i`, counted row by row. The bits are stored
+/// in as many words as the highest tile needs, so boards can have any number of tiles.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Mask {
+    /// The last word is never zero, so equal masks have equal words
+    words: Vec<u64>,
+}
+
+impl Mask {
+    pub fn set(&mut self, tile: usize) {
+        let word = tile / 64;
+        if word >= self.words.len() {
+            self.words.resize(word + 1, 0);
+        }
+        self.words[word] |= 1 << (tile % 64);
+    }
+
+    pub fn contains(&self, tile: usize) -> bool {
+        self.words
+            .get(tile / 64)
+            .is_some_and(|word| word & 1 << (tile % 64) != 0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    /// Whether all tiles of this mask are in `other` as well.
+    pub fn is_subset(&self, other: &Mask) -> bool {
+        self.words.len() <= other.words.len()
+            && self
+                .words
+                .iter()
+                .zip(&other.words)
+                .all(|(a, b)| a & b == *a)
+    }
+
+    /// One after the highest tile, 0 for an empty mask.
+    pub fn end(&self) -> usize {
+        match self.words.last() {
+            Some(last) => 64 * self.words.len() - last.leading_zeros() as usize,
+            None => 0,
+        }
+    }
+
+    /// Parses a binary (radix 2) or hexadecimal (radix 16) number of any length.
+    pub fn from_str_radix(digits: &str, radix: u32) -> Option<Mask> {
+        let bits_per_digit = match radix {
+            2 => 1,
+            16 => 4,
+            _ => return None,
+        };
+        if digits.is_empty() {
+            return None;
+        }
+
+        let mut mask = Mask::default();
+        for (idx, c) in digits.chars().rev().enumerate() {
+            let digit = c.to_digit(radix)?;
+            for bit in 0..bits_per_digit {
+                if digit >> bit & 1 == 1 {
+                    mask.set(idx * bits_per_digit + bit);
+                }
+            }
+        }
+        Some(mask)
+    }
+}
+
+impl FromIterator<usize> for Mask {
+    fn from_iter<I: IntoIterator<Item = usize>>(tiles: I) -> Self {
+        let mut mask = Mask::default();
+        for tile in tiles {
+            mask.set(tile);
+        }
+        mask
+    }
+}
+
+#[test]
+fn test_mask() {
+    let mask: Mask = [0, 3, 200].into_iter().collect();
+    assert!(mask.contains(3) && mask.contains(200));
+    assert!(!mask.contains(1) && !mask.contains(1000));
+    assert_eq!(mask.end(), 201);
+    assert_eq!(Mask::default().end(), 0);
+
+    let marks: Mask = (0..256).collect();
+    assert!(mask.is_subset(&marks));
+    assert!(!marks.is_subset(&mask));
+    assert!(Mask::default().is_subset(&mask));
+
+    assert_eq!(
+        Mask::from_str_radix("1001", 2),
+        Some([0, 3].into_iter().collect())
+    );
+    assert_eq!(
+        Mask::from_str_radix("0001", 16),
+        Mask::from_str_radix("1", 2)
+    );
+    let wide = format!("8{}", "0".repeat(49));
+    assert_eq!(
+        Mask::from_str_radix(&wide, 16).map(|mask| mask.end()),
+        Some(200)
+    );
+    assert_eq!(Mask::from_str_radix("12", 2), None);
+    assert_eq!(Mask::from_str_radix("", 16), None);
+}
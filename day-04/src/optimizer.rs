@@ -0,0 +1,272 @@
+use crate::rules::WinRule;
+use crate::{Board, Mask};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Goal {
+    First,
+    Last,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Plan {
+    /// The numbers to draw, in this order
+    Order(Vec<u8>),
+    /// Another board always wins no later than the target
+    Impossible,
+}
+
+/// Finds the shortest draw order that makes the `target` board win first or last, where boards
+/// that win at the same time as the target count as winning before it.
+///
+/// To win first, it is enough to draw the numbers of one win mask of the target, and the mask
+/// with the fewest numbers that doesn't let another board win with them is the best choice.
+/// Drawing more numbers can only make other boards win earlier.
+///
+/// To win last, every other board has to win with the numbers of one of its win masks before the
+/// target wins, so the order is the union of one win mask per other board, followed by the missing
+/// numbers of the cheapest win mask of the target. Finding the smallest such union is a set cover
+/// problem, which is solved exactly by `LastSearch`.
+pub fn shortest_order(boards: &[Board], rules: &[WinRule], target: usize, goal: Goal) -> Plan {
+    let target_board = &boards[target];
+    let others = (0..boards.len()).filter(|&idx| idx != target);
+
+    let target_masks = target_board.win_masks(rules);
+    if target_masks.is_empty() {
+        return Plan::Impossible;
+    }
+
+    match goal {
+        Goal::First => target_masks
+            .into_iter()
+            .map(|mask| mask_numbers(target_board, &mask))
+            .filter(|nums| {
+                let drawn = nums.iter().copied().collect();
+                others.clone().all(|idx| !boards[idx].is_won(&drawn, rules))
+            })
+            .min_by_key(Vec::len)
+            .map_or(Plan::Impossible, Plan::Order),
+        Goal::Last => {
+            let numbers_of = |board: &Board| -> Vec<Vec<u8>> {
+                board
+                    .win_masks(rules)
+                    .iter()
+                    .map(|mask| mask_numbers(board, mask))
+                    .collect()
+            };
+            let with_sets = |masks: Vec<Vec<u8>>| -> Vec<(NumberSet, Vec<u8>)> {
+                masks
+                    .into_iter()
+                    .map(|nums| (NumberSet::from(nums.as_slice()), nums))
+                    .collect()
+            };
+            let mut search = LastSearch {
+                target: with_sets(numbers_of(target_board)),
+                others: others
+                    .map(|idx| with_sets(numbers_of(&boards[idx])))
+                    .collect(),
+                path: vec![],
+                best: None,
+            };
+            search.run(NumberSet::default());
+            search
+                .best
+                .map_or(Plan::Impossible, |(_, order)| Plan::Order(order))
+        }
+    }
+}
+
+/// A set of numbers, one bit per possible `u8`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+struct NumberSet([u64; 4]);
+
+impl NumberSet {
+    fn insert(&mut self, num: u8) {
+        self.0[num as usize / 64] |= 1 << (num % 64);
+    }
+
+    fn len(&self) -> usize {
+        self.0.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    fn union(&self, other: &NumberSet) -> NumberSet {
+        NumberSet(std::array::from_fn(|i| self.0[i] | other.0[i]))
+    }
+
+    /// The numbers of this set that are not in `other`.
+    fn minus(&self, other: &NumberSet) -> NumberSet {
+        NumberSet(std::array::from_fn(|i| self.0[i] & !other.0[i]))
+    }
+
+    fn is_subset(&self, other: &NumberSet) -> bool {
+        self.minus(other) == NumberSet::default()
+    }
+
+    fn is_disjoint(&self, other: &NumberSet) -> bool {
+        (0..4).all(|i| self.0[i] & other.0[i] == 0)
+    }
+}
+
+impl From<&[u8]> for NumberSet {
+    fn from(nums: &[u8]) -> Self {
+        let mut set = NumberSet::default();
+        nums.iter().for_each(|&num| set.insert(num));
+        set
+    }
+}
+
+/// Branch and bound search for the shortest order that makes the target win last.
+///
+/// Every step picks the other board that isn't won yet with the fewest win masks that don't make
+/// the target win, and tries each of those masks, cheapest first, so every board gets exactly one
+/// mask unless it is won by the masks of other boards. Branches are cut when they can't beat the
+/// best order so far. The search still takes exponential time in the number of boards: a dozen
+/// boards take seconds, and all boards of the puzzle input are out of reach.
+struct LastSearch {
+    /// The numbers of every win mask of the target, as a set and in the order of the tiles
+    target: Vec<(NumberSet, Vec<u8>)>,
+    /// The same for every other board
+    others: Vec<Vec<(NumberSet, Vec<u8>)>>,
+    /// Board and mask index of the masks that are drawn, in the order in which they were picked
+    path: Vec<(usize, usize)>,
+    /// Length and numbers of the shortest order so far
+    best: Option<(usize, Vec<u8>)>,
+}
+
+impl LastSearch {
+    fn target_is_won(&self, drawn: &NumberSet) -> bool {
+        self.target.iter().any(|(mask, _)| mask.is_subset(drawn))
+    }
+
+    fn run(&mut self, drawn: NumberSet) {
+        // For every board that hasn't won yet: the masks that don't make the target win, the
+        // fewest numbers any of them adds, and all numbers they add
+        let mut left: Vec<(usize, Vec<usize>, usize, NumberSet)> = vec![];
+        for (board, masks) in self.others.iter().enumerate() {
+            if masks.iter().any(|(mask, _)| mask.is_subset(&drawn)) {
+                continue;
+            }
+            let allowed: Vec<usize> = (0..masks.len())
+                .filter(|&idx| !self.target_is_won(&drawn.union(&masks[idx].0)))
+                .collect();
+            let Some(min_new) = allowed
+                .iter()
+                .map(|&idx| masks[idx].0.minus(&drawn).len())
+                .min()
+            else {
+                return;
+            };
+            let all_new = allowed.iter().fold(NumberSet::default(), |all, &idx| {
+                all.union(&masks[idx].0.minus(&drawn))
+            });
+            left.push((board, allowed, min_new, all_new));
+        }
+
+        if left.is_empty() {
+            // The target wins with the cheapest of its masks, see `shortest_order`
+            let (completion, nums) = self
+                .target
+                .iter()
+                .map(|(mask, nums)| (mask.minus(&drawn).len(), nums))
+                .min_by_key(|(completion, _)| *completion)
+                .expect("the target has win masks");
+            let len = drawn.len() + completion;
+            if self.best.as_ref().is_some_and(|(best, _)| *best <= len) {
+                return;
+            }
+
+            let mut order: Vec<u8> = vec![];
+            let masks = self
+                .path
+                .iter()
+                .map(|&(board, idx)| &self.others[board][idx].1);
+            for &num in masks.chain([nums]).flatten() {
+                if !order.contains(&num) {
+                    order.push(num);
+                }
+            }
+            self.best = Some((len, order));
+            return;
+        }
+
+        // Boards whose masks add no common numbers need numbers of their own, and the target
+        // needs at least one number that isn't drawn for the other boards
+        left.sort_by_key(|&(_, _, min_new, _)| std::cmp::Reverse(min_new));
+        let mut bound = drawn.len() + 1;
+        let mut counted = NumberSet::default();
+        for (_, _, min_new, all_new) in &left {
+            if all_new.is_disjoint(&counted) {
+                bound += min_new;
+                counted = counted.union(all_new);
+            }
+        }
+        if self.best.as_ref().is_some_and(|(best, _)| *best <= bound) {
+            return;
+        }
+
+        let (board, mut allowed, _, _) = left
+            .into_iter()
+            .min_by_key(|(_, allowed, _, _)| allowed.len())
+            .expect("a board is left");
+        allowed.sort_by_key(|&idx| self.others[board][idx].0.minus(&drawn).len());
+        for idx in allowed {
+            self.path.push((board, idx));
+            self.run(drawn.union(&self.others[board][idx].0));
+            self.path.pop();
+        }
+    }
+}
+
+/// The numbers on the tiles of the mask, without duplicates, in the order of the tiles.
+fn mask_numbers(board: &Board, mask: &Mask) -> Vec<u8> {
+    let mut nums = vec![];
+    for (tile, &num) in board.tiles.iter().enumerate() {
+        if mask.contains(tile) && !nums.contains(&num) {
+            nums.push(num);
+        }
+    }
+    nums
+}
+
+#[test]
+fn test_shortest_order() {
+    use crate::game::{BingoGame, Outcome};
+    use crate::rules::DEFAULT_RULES;
+
+    let boards: [Board; 3] = [
+        "1 2 3\n4 5 6\n7 8 9".parse().unwrap(),
+        "1 2\n3 10".parse().unwrap(),
+        "4 5\n6 11".parse().unwrap(),
+    ];
+
+    // The first row of board 1 would let board 2 win as well
+    let plan = shortest_order(&boards, &DEFAULT_RULES, 0, Goal::First);
+    assert_eq!(plan, Plan::Order(vec![7, 8, 9]));
+    let plan = shortest_order(&boards, &DEFAULT_RULES, 1, Goal::First);
+    assert_eq!(plan, Plan::Order(vec![1, 2]));
+
+    let Plan::Order(order) = shortest_order(&boards, &DEFAULT_RULES, 0, Goal::Last) else {
+        panic!("no order for the last winner");
+    };
+    let result = BingoGame::play(&order, &boards, &DEFAULT_RULES);
+    assert!(matches!(result.last(), Outcome::Winner(winner) if winner.board == 0));
+
+    // Drawing the cheap 5 of board 3 first leaves only masks of board 2 that make the target win
+    let boards: [Board; 3] = [
+        "1 3\n5 0".parse().unwrap(),
+        "1 3\n2 0".parse().unwrap(),
+        "1 0\n5 5".parse().unwrap(),
+    ];
+    let Plan::Order(order) = shortest_order(&boards, &DEFAULT_RULES, 0, Goal::Last) else {
+        panic!("no order for the last winner");
+    };
+    assert_eq!(order.len(), 4);
+    let result = BingoGame::play(&order, &boards, &DEFAULT_RULES);
+    assert!(matches!(result.last(), Outcome::Winner(winner) if winner.board == 0));
+
+    // Every line of board 2 is a line of board 1
+    let boards: [Board; 2] = ["1 2\n3 4".parse().unwrap(), "1 2\n3 4".parse().unwrap()];
+    let plan = shortest_order(&boards, &DEFAULT_RULES, 0, Goal::First);
+    assert_eq!(plan, Plan::Impossible);
+    let plan = shortest_order(&boards, &DEFAULT_RULES, 0, Goal::Last);
+    assert_eq!(plan, Plan::Impossible);
+}
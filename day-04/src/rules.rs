@@ -0,0 +1,111 @@
+use crate::Mask;
+
+/// A rule for when a board wins. A board wins as soon as all tiles of one of the masks of any of
+/// the rules of the game are marked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WinRule {
+    Rows,
+    Columns,
+    /// Both diagonals, only on square boards
+    Diagonals,
+    FourCorners,
+    /// All tiles of the board
+    Blackout,
+    /// User defined pattern where bit `i` is tile `i`, counted row by row. Patterns with tiles
+    /// outside of a board never win on that board.
+    Pattern(Mask),
+}
+
+/// The rules of the puzzle
+pub const DEFAULT_RULES: [WinRule; 2] = [WinRule::Rows, WinRule::Columns];
+
+impl WinRule {
+    /// Masks of all tile combinations that win with this rule on a board of the given size.
+    pub fn masks(&self, width: usize, height: usize) -> Vec<Mask> {
+        let tile = |row: usize, col: usize| width * row + col;
+        let num_tiles = width * height;
+
+        match self {
+            WinRule::Rows => (0..height)
+                .map(|row| (0..width).map(|col| tile(row, col)).collect())
+                .collect(),
+            WinRule::Columns => (0..width)
+                .map(|col| (0..height).map(|row| tile(row, col)).collect())
+                .collect(),
+            WinRule::Diagonals if width == height => vec![
+                (0..width).map(|i| tile(i, i)).collect(),
+                (0..width).map(|i| tile(i, width - 1 - i)).collect(),
+            ],
+            WinRule::Diagonals => vec![],
+            WinRule::FourCorners => vec![[
+                tile(0, 0),
+                tile(0, width - 1),
+                tile(height - 1, 0),
+                tile(height - 1, width - 1),
+            ]
+            .into_iter()
+            .collect()],
+            WinRule::Blackout => vec![(0..num_tiles).collect()],
+            // An empty pattern would win without marking anything
+            WinRule::Pattern(mask) if mask.is_empty() || mask.end() > num_tiles => vec![],
+            WinRule::Pattern(mask) => vec![mask.clone()],
+        }
+    }
+}
+
+/// Parses a comma separated list of rules. Patterns are written as `pattern:<mask>`, where the
+/// mask is a binary (`0b...`) or hexadecimal (`0x...`) number.
+pub fn parse_rules(input: &str) -> Option<Vec<WinRule>> {
+    input
+        .split(',')
+        .map(|rule| match rule {
+            "rows" => Some(WinRule::Rows),
+            "columns" => Some(WinRule::Columns),
+            "diagonals" => Some(WinRule::Diagonals),
+            "corners" => Some(WinRule::FourCorners),
+            "blackout" => Some(WinRule::Blackout),
+            _ => {
+                let mask = rule.strip_prefix("pattern:")?;
+                let mask = if let Some(binary) = mask.strip_prefix("0b") {
+                    Mask::from_str_radix(binary, 2)?
+                } else {
+                    Mask::from_str_radix(mask.strip_prefix("0x")?, 16)?
+                };
+                Some(WinRule::Pattern(mask))
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn test_masks() {
+    let mask = |bits: &str| Mask::from_str_radix(bits, 2).unwrap();
+    assert_eq!(
+        WinRule::Rows.masks(3, 2),
+        vec![mask("000111"), mask("111000")]
+    );
+    assert_eq!(
+        WinRule::Columns.masks(3, 2),
+        vec![mask("001001"), mask("010010"), mask("100100")]
+    );
+    assert_eq!(
+        WinRule::Diagonals.masks(3, 3),
+        vec![mask("100010001"), mask("001010100")]
+    );
+    assert_eq!(WinRule::Diagonals.masks(3, 2), vec![]);
+    assert_eq!(WinRule::FourCorners.masks(3, 2), vec![mask("101101")]);
+    assert_eq!(WinRule::Blackout.masks(3, 2), vec![mask("111111")]);
+    assert_eq!(WinRule::Blackout.masks(20, 20), vec![(0..400).collect()]);
+    assert_eq!(WinRule::Pattern(mask("1000000")).masks(3, 2), vec![]);
+    assert_eq!(WinRule::Pattern(mask("0")).masks(3, 2), vec![]);
+
+    assert_eq!(
+        parse_rules("rows,pattern:0b101,pattern:0xff"),
+        Some(vec![
+            WinRule::Rows,
+            WinRule::Pattern(mask("101")),
+            WinRule::Pattern(mask("11111111"))
+        ])
+    );
+    assert_eq!(parse_rules("rows,unknown"), None);
+}
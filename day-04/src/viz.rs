@@ -0,0 +1,185 @@
+use std::collections::HashSet;
+use std::io::{self, BufRead, Write};
+use std::thread;
+use std::time::Duration;
+
+use crate::rules::WinRule;
+use crate::{Board, Mask};
+
+/// Number of boards that are drawn next to each other
+const BOARDS_PER_ROW: usize = 6;
+/// Space between two boards next to each other
+const GAP: &str = "    ";
+
+const CLEAR_SCREEN: &str = "\x1b[2J\x1b[H";
+const RESET: &str = "\x1b[0m";
+/// Bold yellow
+const MARKED: &str = "\x1b[1;33m";
+/// Bold black on green
+const WINNING_LINE: &str = "\x1b[1;30;42m";
+
+/// Replays the game in the terminal draw by draw. Without a delay the next number is drawn when
+/// enter is pressed, `q` quits. The replay ends when every board has won or the numbers run out.
+pub fn replay(
+    nums: &[u8],
+    boards: &[Board],
+    rules: &[WinRule],
+    delay: Option<Duration>,
+) -> io::Result<()> {
+    let mut drawn_numbers = HashSet::with_capacity(nums.len());
+    // The winning line and turn of every board that has won
+    let mut wins: Vec<Option<(Mask, usize)>> = vec![None; boards.len()];
+    let mut stdout = io::stdout();
+    let mut stdin = io::stdin().lock();
+
+    for (idx, &num) in nums.iter().enumerate() {
+        let turn = idx + 1;
+        drawn_numbers.insert(num);
+        for (board, win) in boards.iter().zip(wins.iter_mut()) {
+            if win.is_none() {
+                *win = board
+                    .winning_mask(&drawn_numbers, rules)
+                    .map(|mask| (mask, turn));
+            }
+        }
+
+        write!(stdout, "{}", CLEAR_SCREEN)?;
+        write!(stdout, "Draw {} of {}: {}\n\n", turn, nums.len(), num)?;
+        write!(stdout, "{}", render(boards, &drawn_numbers, &wins))?;
+        stdout.flush()?;
+
+        if wins.iter().all(Option::is_some) {
+            break;
+        }
+
+        match delay {
+            Some(delay) => thread::sleep(delay),
+            None => {
+                write!(stdout, "Press enter for the next draw or q to quit ")?;
+                stdout.flush()?;
+                let mut line = String::new();
+                if stdin.read_line(&mut line)? == 0 || line.trim() == "q" {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    let num_won = wins.iter().filter(|win| win.is_some()).count();
+    writeln!(stdout, "{} of {} boards won", num_won, boards.len())
+}
+
+/// Draws the boards in rows of `BOARDS_PER_ROW`. Marked tiles are highlighted and the line that
+/// made a board win stands out from the other marked tiles.
+fn render(boards: &[Board], drawn_numbers: &HashSet<u8>, wins: &[Option<(Mask, usize)>]) -> String {
+    let mut text = String::new();
+
+    for (row_idx, row) in boards.chunks(BOARDS_PER_ROW).enumerate() {
+        let first_board = row_idx * BOARDS_PER_ROW;
+        let height = row.iter().map(|board| board.height).max().unwrap_or(0);
+
+        let labels: Vec<String> = (first_board..first_board + row.len())
+            .map(|board_idx| match wins[board_idx] {
+                Some((_, turn)) => format!("Board {} won at {}", board_idx + 1, turn),
+                None => format!("Board {}", board_idx + 1),
+            })
+            .collect();
+        // Every board takes `cell_width` characters per tile, unless its label is wider
+        let widths: Vec<usize> = row
+            .iter()
+            .zip(&labels)
+            .map(|(board, label)| label.len().max(board.width * cell_width(board)))
+            .collect();
+
+        let labels: Vec<String> = labels
+            .iter()
+            .zip(&widths)
+            .map(|(label, &width)| format!("{:<width$}", label, width = width))
+            .collect();
+        text.push_str(labels.join(GAP).trim_end());
+        text.push('\n');
+
+        for line in 0..height {
+            let lines: Vec<String> = row
+                .iter()
+                .zip(&widths)
+                .enumerate()
+                .map(|(idx, (board, &width))| {
+                    let win_mask = wins[first_board + idx].as_ref().map(|(mask, _)| mask);
+                    let padding = " ".repeat(width - board.width * cell_width(board));
+                    render_line(board, line, drawn_numbers, win_mask) + &padding
+                })
+                .collect();
+            text.push_str(lines.join(GAP).trim_end());
+            text.push('\n');
+        }
+        text.push('\n');
+    }
+
+    text
+}
+
+/// Characters per tile of the board: a space and the digits of the largest number, but at least
+/// two digits, so boards with small numbers line up with the usual boards.
+fn cell_width(board: &Board) -> usize {
+    let max = board.tiles.iter().max().copied().unwrap_or(0);
+    max.to_string().len().max(2) + 1
+}
+
+/// One line of a board, or spaces for boards that are less high than others in the same row.
+/// The tiles of `win_mask` are the winning line.
+fn render_line(
+    board: &Board,
+    line: usize,
+    drawn_numbers: &HashSet<u8>,
+    win_mask: Option<&Mask>,
+) -> String {
+    let cell_width = cell_width(board);
+    if line >= board.height {
+        return " ".repeat(board.width * cell_width);
+    }
+    let digits = cell_width - 1;
+
+    let mut text = String::new();
+    for col in 0..board.width {
+        let tile = line * board.width + col;
+        let num = board.tiles[tile];
+        if win_mask.is_some_and(|mask| mask.contains(tile)) {
+            text.push_str(&format!(" {}{:>digits$}{}", WINNING_LINE, num, RESET));
+        } else if drawn_numbers.contains(&num) {
+            text.push_str(&format!(" {}{:>digits$}{}", MARKED, num, RESET));
+        } else {
+            text.push_str(&format!(" {:>digits$}", num));
+        }
+    }
+    text
+}
+
+#[test]
+fn test_render() {
+    let boards: [Board; 2] = ["1 2\n3 4".parse().unwrap(), "5 6".parse().unwrap()];
+    let drawn_numbers = HashSet::from([1, 2, 5]);
+    let wins = [Some(([0, 1].into_iter().collect(), 2)), None];
+
+    let text = render(&boards, &drawn_numbers, &wins);
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines[0], "Board 1 won at 2    Board 2");
+    assert_eq!(
+        lines[1],
+        format!(
+            " {w} 1{r} {w} 2{r}               {m} 5{r}  6",
+            w = WINNING_LINE,
+            m = MARKED,
+            r = RESET
+        )
+    );
+    assert_eq!(lines[2], "  3  4");
+
+    // Three digit numbers take four characters per tile, on every line of the board
+    let boards: [Board; 2] = ["100 2\n3 4".parse().unwrap(), "5 6".parse().unwrap()];
+    let text = render(&boards, &HashSet::new(), &[None, None]);
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines[0], "Board 1     Board 2");
+    assert_eq!(lines[1], " 100   2      5  6");
+    assert_eq!(lines[2], "   3   4");
+}
@@ -0,0 +1,164 @@
+use crate::grid::Grid;
+use crate::line_iter::LineIter;
+use crate::raster::{Bresenham, Raster};
+use crate::render::Area;
+use crate::{is_straight_or_diagonal, Point, PointPair};
+
+/// The number of lines on every point, which can also tell which lines are on a point.
+pub struct Coverage<'a> {
+    /// The index of every line in the input, and the line
+    lines: Vec<(usize, &'a PointPair)>,
+    raster: Raster,
+    grid: Grid,
+}
+
+impl<'a> Coverage<'a> {
+    /// Rasterizes all lines. Only horizontal and vertical lines are used, when `include_diagonals`
+    /// is false. The lines are split across `threads` threads, see `Grid::add_parallel`.
+    pub fn with_threads(
+        line_points: &'a [PointPair],
+        include_diagonals: bool,
+        raster: Raster,
+        threads: usize,
+    ) -> Self {
+        let lines: Vec<(usize, &PointPair)> = line_points
+            .iter()
+            .enumerate()
+            .filter(|(_, ((x1, y1), (x2, y2)))| include_diagonals || x1 == x2 || y1 == y2)
+            .collect();
+
+        // A vector over the bounding box is ~1.5x faster than a hash map, but uses more memory,
+        // because many points won't have any line on it. So the grid only uses a vector if the
+        // bounding box is small enough. Lines never leave the bounding box of their end points.
+        let end_points = || lines.iter().flat_map(|&(_, &(p1, p2))| [p1, p2]);
+        let mut grid = Grid::covering(end_points());
+        if threads <= 1 {
+            for &(_, line) in &lines {
+                for_each_point(line, raster, |point| grid.add(point));
+            }
+        } else {
+            let chunk_size = lines.len().div_ceil(threads).max(1);
+            grid.add_parallel(lines.chunks(chunk_size), |&(_, line), add| {
+                for_each_point(line, raster, add)
+            });
+        }
+
+        Coverage {
+            lines,
+            raster,
+            grid,
+        }
+    }
+
+    /// Number of lines on the point.
+    pub fn get(&self, point: Point) -> u32 {
+        self.grid.get(point)
+    }
+
+    /// The bounding box of all lines, `None` if there are no lines.
+    pub fn bounds(&self) -> Option<Area> {
+        let mut points = self.lines.iter().flat_map(|&(_, &(p1, p2))| [p1, p2]);
+        let first = points.next()?;
+        Some(points.fold(
+            Area {
+                min: first,
+                max: first,
+            },
+            |Area { min, max }, (x, y)| Area {
+                min: (min.0.min(x), min.1.min(y)),
+                max: (max.0.max(x), max.1.max(y)),
+            },
+        ))
+    }
+
+    /// Number of points with at least `k` lines on them.
+    pub fn num_covered_by(&self, k: u32) -> usize {
+        self.grid.num_at_least(k)
+    }
+
+    /// The highest number of lines on a point, and all points with that many lines, sorted by
+    /// row and column. `None` if there are no lines.
+    pub fn max_coverage(&self) -> Option<(u32, Vec<Point>)> {
+        let mut max = 0;
+        let mut points = vec![];
+        self.grid.for_each_count(|point, count| {
+            if count > max {
+                max = count;
+                points.clear();
+            }
+            if count == max {
+                points.push(point);
+            }
+        });
+
+        points.sort_unstable_by_key(|&(x, y)| (y, x));
+        Some((max, points)).filter(|_| max > 0)
+    }
+
+    /// Indices of the lines on the point, in the order of the input.
+    pub fn lines_at(&self, point: Point) -> Vec<usize> {
+        if self.grid.get(point) == 0 {
+            return vec![];
+        }
+
+        let (x, y) = point;
+        self.lines
+            .iter()
+            .filter(|(_, &((x1, y1), (x2, y2)))| {
+                x1.min(x2) <= x && x <= x1.max(x2) && y1.min(y2) <= y && y <= y1.max(y2)
+            })
+            .filter(|(_, line)| {
+                let mut is_on_line = false;
+                for_each_point(line, self.raster, |p| is_on_line |= p == point);
+                is_on_line
+            })
+            .map(|&(idx, _)| idx)
+            .collect()
+    }
+}
+
+fn for_each_point(line: &PointPair, raster: Raster, f: impl FnMut(Point)) {
+    // The lines of the puzzle don't need a general rasterizer
+    if is_straight_or_diagonal(line) || raster == Raster::Lattice {
+        LineIter::new(line).for_each(f);
+    } else {
+        Bresenham::new(line).for_each(f);
+    }
+}
+
+#[test]
+fn test_coverage() {
+    let lines = [
+        ((0, 0), (4, 0)),
+        ((2, -2), (2, 2)),
+        ((0, -2), (4, 2)),
+        ((0, 2), (6, -1)),
+    ];
+
+    let coverage = Coverage::with_threads(&lines, false, Raster::Lattice, 1);
+    assert_eq!(coverage.num_covered_by(2), 1);
+    assert_eq!(coverage.max_coverage(), Some((2, vec![(2, 0)])));
+    assert_eq!(coverage.lines_at((2, 1)), vec![1]);
+
+    let coverage = Coverage::with_threads(&lines, true, Raster::Lattice, 1);
+    assert_eq!(coverage.num_covered_by(1), 5 + 5 + 5 + 4 - 4);
+    assert_eq!(coverage.num_covered_by(3), 1);
+    assert_eq!(coverage.max_coverage(), Some((3, vec![(2, 0)])));
+    assert_eq!(coverage.lines_at((2, 0)), vec![0, 1, 2]);
+    assert_eq!(coverage.lines_at((4, 0)), vec![0, 3]);
+    assert_eq!(coverage.lines_at((4, 2)), vec![2]);
+    assert_eq!(coverage.lines_at((5, 5)), vec![]);
+
+    assert_eq!(
+        Coverage::with_threads(&[], true, Raster::Lattice, 1).max_coverage(),
+        None
+    );
+
+    for threads in [2, 3, 8] {
+        let parallel = Coverage::with_threads(&lines, true, Raster::Bresenham, threads);
+        assert_eq!(
+            parallel.grid,
+            Coverage::with_threads(&lines, true, Raster::Bresenham, 1).grid
+        );
+    }
+}
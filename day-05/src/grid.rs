@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::thread;
+
+use crate::Point;
+
+/// Bounding boxes with more points than this are counted in a hash map instead of a vector.
+const MAX_DENSE_POINTS: i64 = 1 << 24;
+
+/// Index of a point inside of the bounding box of a dense grid.
+fn dense_index(min: Point, width: usize, (x, y): Point) -> usize {
+    let col = (x as i64 - min.0 as i64) as usize;
+    let row = (y as i64 - min.1 as i64) as usize;
+    row * width + col
+}
+
+/// Counts the lines on every point of a bounding box.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Grid {
+    /// One counter per point of the bounding box, row by row, starting at `min`
+    Dense {
+        min: Point,
+        width: usize,
+        counts: Vec<u32>,
+    },
+    /// Only points with at least one line are stored
+    Sparse(HashMap<Point, u32>),
+}
+
+impl Grid {
+    /// A grid for the bounding box of the given points. Coordinates can be negative, as they are
+    /// offset by the minimum of the box.
+    pub fn covering(points: impl Iterator<Item = Point>) -> Self {
+        let mut min = (i32::MAX, i32::MAX);
+        let mut max = (i32::MIN, i32::MIN);
+        for (x, y) in points {
+            min = (min.0.min(x), min.1.min(y));
+            max = (max.0.max(x), max.1.max(y));
+        }
+
+        // No points at all
+        if min.0 > max.0 {
+            return Grid::Sparse(HashMap::new());
+        }
+
+        // Calculated with i64, because the size of the box can exceed i32
+        let width = max.0 as i64 - min.0 as i64 + 1;
+        let height = max.1 as i64 - min.1 as i64 + 1;
+        match width.checked_mul(height) {
+            Some(num_points) if num_points <= MAX_DENSE_POINTS => Grid::Dense {
+                min,
+                width: width as usize,
+                counts: vec![0; num_points as usize],
+            },
+            _ => Grid::Sparse(HashMap::new()),
+        }
+    }
+
+    /// Adds a line on the point, which has to be inside of the bounding box.
+    pub fn add(&mut self, point: Point) {
+        match self {
+            Grid::Dense { min, width, counts } => counts[dense_index(*min, *width, point)] += 1,
+            Grid::Sparse(counts) => *counts.entry(point).or_insert(0) += 1,
+        }
+    }
+
+    /// Adds the points of every chunk on a thread of its own, with `for_each_point` calling its
+    /// callback for every point of an item of a chunk.
+    ///
+    /// The threads share the counters of a dense grid as atomics, so the bounding box is only
+    /// allocated once, no matter how many threads there are. A sparse grid only stores the points
+    /// that are added, so every thread fills a hash map of its own, and they are added up at the
+    /// end.
+    pub fn add_parallel<'a, T, F>(
+        &mut self,
+        chunks: impl Iterator<Item = &'a [T]>,
+        for_each_point: F,
+    ) where
+        T: Sync + 'a,
+        F: Fn(&T, &mut dyn FnMut(Point)) + Sync,
+    {
+        let for_each_point = &for_each_point;
+        match self {
+            Grid::Dense { min, width, counts } => {
+                let (min, width) = (*min, *width);
+                let shared: Vec<AtomicU32> = std::mem::take(counts)
+                    .into_iter()
+                    .map(AtomicU32::new)
+                    .collect();
+                thread::scope(|scope| {
+                    for chunk in chunks {
+                        let shared = &shared;
+                        scope.spawn(move || {
+                            for item in chunk {
+                                for_each_point(item, &mut |point| {
+                                    shared[dense_index(min, width, point)]
+                                        .fetch_add(1, Ordering::Relaxed);
+                                });
+                            }
+                        });
+                    }
+                });
+                *counts = shared.into_iter().map(AtomicU32::into_inner).collect();
+            }
+            Grid::Sparse(counts) => {
+                let thread_counts: Vec<HashMap<Point, u32>> = thread::scope(|scope| {
+                    let handles: Vec<_> = chunks
+                        .map(|chunk| {
+                            scope.spawn(move || {
+                                let mut counts = HashMap::new();
+                                for item in chunk {
+                                    for_each_point(item, &mut |point| {
+                                        *counts.entry(point).or_insert(0) += 1
+                                    });
+                                }
+                                counts
+                            })
+                        })
+                        .collect();
+                    handles
+                        .into_iter()
+                        .map(|handle| handle.join().expect("rasterizing thread panicked"))
+                        .collect()
+                });
+                for other in thread_counts {
+                    for (point, other) in other {
+                        *counts.entry(point).or_insert(0) += other;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Number of lines on the point, which can be outside of the bounding box.
+    pub fn get(&self, (x, y): Point) -> u32 {
+        match self {
+            Grid::Dense { min, width, counts } => {
+                let col = x as i64 - min.0 as i64;
+                let row = y as i64 - min.1 as i64;
+                if col < 0 || row < 0 || col >= *width as i64 {
+                    return 0;
+                }
+                let idx = row as usize * *width + col as usize;
+                counts.get(idx).copied().unwrap_or(0)
+            }
+            Grid::Sparse(counts) => counts.get(&(x, y)).copied().unwrap_or(0),
+        }
+    }
+
+    /// Calls `f` for every point with at least one line on it, together with the number of lines.
+    pub fn for_each_count(&self, mut f: impl FnMut(Point, u32)) {
+        match self {
+            Grid::Dense { min, width, counts } => {
+                for (idx, &count) in counts.iter().enumerate() {
+                    if count > 0 {
+                        let x = min.0 as i64 + (idx % width) as i64;
+                        let y = min.1 as i64 + (idx / width) as i64;
+                        f((x as i32, y as i32), count);
+                    }
+                }
+            }
+            Grid::Sparse(counts) => counts.iter().for_each(|(&point, &count)| f(point, count)),
+        }
+    }
+
+    /// Number of points with at least `k` lines on them.
+    pub fn num_at_least(&self, k: u32) -> usize {
+        match self {
+            Grid::Dense { counts, .. } => counts.iter().filter(|count| **count >= k).count(),
+            Grid::Sparse(counts) => counts.values().filter(|count| **count >= k).count(),
+        }
+    }
+}
+
+#[test]
+fn test_grid() {
+    let points = [(-2, 5), (3, -1), (3, 5), (-2, 5)];
+    let mut grid = Grid::covering(points.iter().copied());
+    assert!(matches!(
+        grid,
+        Grid::Dense {
+            min: (-2, -1),
+            width: 6,
+            ..
+        }
+    ));
+    points.iter().for_each(|&p| grid.add(p));
+    assert_eq!(grid.num_at_least(2), 1);
+    assert_eq!(grid.get((-2, 5)), 2);
+    assert_eq!(grid.get((4, 5)), 0);
+
+    let points = [(-1_000_000, 0), (1_000_000, 1_000), (1_000_000, 1_000)];
+    let mut grid = Grid::covering(points.iter().copied());
+    assert!(matches!(grid, Grid::Sparse(_)));
+    points.iter().for_each(|&p| grid.add(p));
+    assert_eq!(grid.num_at_least(2), 1);
+    assert_eq!(grid.get((1_000_000, 1_000)), 2);
+}
@@ -0,0 +1,254 @@
+use std::collections::HashMap;
+
+use crate::line_iter::gcd;
+use crate::{Point, PointPair};
+
+/// A line through lattice points, given by the smallest step from one of its lattice points to
+/// the next, and the cross product of the step with any of its points.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct LineKey {
+    /// Points right, or down for vertical lines
+    step: (i64, i64),
+    offset: i128,
+}
+
+impl LineKey {
+    /// `None` for single points.
+    fn new(&((x1, y1), (x2, y2)): &PointPair) -> Option<Self> {
+        let dx = x2 as i64 - x1 as i64;
+        let dy = y2 as i64 - y1 as i64;
+        let num_steps = gcd(dx.abs(), dy.abs());
+        if num_steps == 0 {
+            return None;
+        }
+
+        let mut step = (dx / num_steps, dy / num_steps);
+        if step.0 < 0 || (step.0 == 0 && step.1 < 0) {
+            step = (-step.0, -step.1);
+        }
+        let offset = step.1 as i128 * x1 as i128 - step.0 as i128 * y1 as i128;
+        Some(LineKey { step, offset })
+    }
+
+    /// Position of a lattice point of the line. Neighbouring lattice points differ by one.
+    fn position(&self, (x, y): Point) -> i64 {
+        match self.step.0 {
+            0 => y as i64,
+            step_x => (x as i64).div_euclid(step_x),
+        }
+    }
+}
+
+/// Counts the lattice points that are on at least two of the lines, without visiting the points
+/// of the lines. This gives the same result as rasterizing the lines with `Raster::Lattice`.
+///
+/// Lines on the same infinite line overlap in intervals of positions, which are found by sorting
+/// their end points. All other overlaps are crossings of two lines, which are found by
+/// intersecting every pair of lines. A crossing can be part of overlapping intervals as well, so
+/// it is only counted if no interval has counted it yet, and subtracted if more than one did.
+///
+/// The crossings are found for one infinite line at a time, which every other line crosses at
+/// most once. So the memory grows with the number of lines, but neither with the number of
+/// crossings nor with the length of the lines.
+pub fn count_overlapping(lines: &[&PointPair]) -> usize {
+    let keys: Vec<Option<LineKey>> = lines.iter().map(|line| LineKey::new(line)).collect();
+
+    // Positions of the lines per infinite line
+    let mut intervals = HashMap::<LineKey, Vec<(i64, i64)>>::new();
+    for (&&(p1, p2), key) in lines.iter().zip(&keys) {
+        if let Some(key) = key {
+            let (start, end) = (key.position(p1), key.position(p2));
+            intervals
+                .entry(*key)
+                .or_default()
+                .push((start.min(end), start.max(end)));
+        }
+    }
+    let overlaps: HashMap<LineKey, Vec<(i64, i64)>> = intervals
+        .into_iter()
+        .map(|(key, intervals)| (key, overlapping_intervals(intervals)))
+        .collect();
+    let mut num_overlapping: i64 = overlaps
+        .values()
+        .flatten()
+        .map(|(start, end)| end - start + 1)
+        .sum();
+
+    // Lines on the same infinite line form a class. Single points don't have an infinite line, so
+    // every single point is a class of its own, told apart by its index.
+    let class_of = |idx: usize| keys[idx].ok_or(idx);
+    let class_order = |class: &Result<LineKey, usize>| match class {
+        Ok(key) => (0, key.step, key.offset),
+        Err(idx) => (1, (*idx as i64, 0), 0),
+    };
+    let mut classes = HashMap::<Result<LineKey, usize>, Vec<usize>>::new();
+    for idx in 0..lines.len() {
+        classes.entry(class_of(idx)).or_default().push(idx);
+    }
+
+    for (class, members) in &classes {
+        // The classes of the other lines crossing this class, by crossing
+        let mut crossings: Vec<(Point, Result<LineKey, usize>)> = (0..lines.len())
+            .filter(|&idx| class_of(idx) != *class)
+            .filter_map(|idx| {
+                let point = members
+                    .iter()
+                    .find_map(|&member| crossing(lines[member], lines[idx]))?;
+                Some((point, class_of(idx)))
+            })
+            .collect();
+        crossings.sort_unstable_by_key(|(point, other)| (*point, class_order(other)));
+        crossings.dedup();
+
+        for crossing_lines in crossings.chunk_by(|(a, _), (b, _)| a == b) {
+            // Every crossing is counted on the first of its classes only
+            let (point, first_other) = &crossing_lines[0];
+            if class_order(first_other) < class_order(class) {
+                continue;
+            }
+
+            let num_counted = std::iter::once(class)
+                .chain(crossing_lines.iter().map(|(_, other)| other))
+                .filter_map(|line| line.as_ref().ok())
+                .filter(|key| {
+                    let position = key.position(*point);
+                    overlaps[key]
+                        .iter()
+                        .any(|&(start, end)| start <= position && position <= end)
+                })
+                .count() as i64;
+            num_overlapping += match num_counted {
+                0 => 1,
+                _ => 1 - num_counted,
+            };
+        }
+    }
+
+    num_overlapping as usize
+}
+
+/// The parts of the number line that are covered by at least two of the intervals, sorted and
+/// without overlaps.
+fn overlapping_intervals(intervals: Vec<(i64, i64)>) -> Vec<(i64, i64)> {
+    let mut changes: Vec<(i64, i32)> = intervals
+        .into_iter()
+        .flat_map(|(start, end)| [(start, 1), (end + 1, -1)])
+        .collect();
+    changes.sort_unstable();
+
+    let mut overlaps = vec![];
+    let mut num_covering = 0;
+    let mut overlap_start = 0;
+    for (position, change) in changes {
+        let was_overlapping = num_covering > 1;
+        num_covering += change;
+        match (was_overlapping, num_covering > 1) {
+            (false, true) => overlap_start = position,
+            (true, false) => overlaps.push((overlap_start, position - 1)),
+            _ => {}
+        }
+    }
+    overlaps
+}
+
+/// The lattice point where two lines on different infinite lines cross, if they do.
+fn crossing(&(p1, p2): &PointPair, &(q1, q2): &PointPair) -> Option<Point> {
+    let to_i128 = |(x, y): Point| (x as i128, y as i128);
+    let cross = |(ax, ay): (i128, i128), (bx, by): (i128, i128)| ax * by - ay * bx;
+    let (p1, p2, q1, q2) = (to_i128(p1), to_i128(p2), to_i128(q1), to_i128(q2));
+
+    let d_p = (p2.0 - p1.0, p2.1 - p1.1);
+    let d_q = (q2.0 - q1.0, q2.1 - q1.1);
+    let p_to_q = (q1.0 - p1.0, q1.1 - p1.1);
+
+    let denom = cross(d_p, d_q);
+    if denom == 0 {
+        // Parallel, or at least one of them is a single point
+        let on_line = |point: (i128, i128), start: (i128, i128), end: (i128, i128)| {
+            let d = (end.0 - start.0, end.1 - start.1);
+            cross((point.0 - start.0, point.1 - start.1), d) == 0
+                && start.0.min(end.0) <= point.0
+                && point.0 <= start.0.max(end.0)
+                && start.1.min(end.1) <= point.1
+                && point.1 <= start.1.max(end.1)
+        };
+        let point = if d_p == (0, 0) && on_line(p1, q1, q2) {
+            p1
+        } else if d_q == (0, 0) && on_line(q1, p1, p2) {
+            q1
+        } else {
+            return None;
+        };
+        return Some((point.0 as i32, point.1 as i32));
+    }
+
+    // The crossing is at `p1 + t * d_p` and `q1 + u * d_q` with `t` and `u` between 0 and 1
+    let t_num = cross(p_to_q, d_q);
+    let u_num = cross(p_to_q, d_p);
+    let in_range = |num: i128| {
+        if denom > 0 {
+            0 <= num && num <= denom
+        } else {
+            denom <= num && num <= 0
+        }
+    };
+    if !in_range(t_num) || !in_range(u_num) {
+        return None;
+    }
+
+    let x = p1.0 * denom + t_num * d_p.0;
+    let y = p1.1 * denom + t_num * d_p.1;
+    if x % denom != 0 || y % denom != 0 {
+        return None;
+    }
+    Some(((x / denom) as i32, (y / denom) as i32))
+}
+
+#[test]
+fn test_count_overlapping() {
+    use crate::line_iter::LineIter;
+
+    let lines = [
+        // The example of the puzzle
+        ((0, 9), (5, 9)),
+        ((8, 0), (0, 8)),
+        ((9, 4), (3, 4)),
+        ((2, 2), (2, 1)),
+        ((7, 0), (7, 4)),
+        ((6, 4), (2, 0)),
+        ((0, 9), (2, 9)),
+        ((3, 4), (1, 4)),
+        ((0, 0), (8, 8)),
+        ((5, 5), (8, 2)),
+        // Other slopes, overlapping lines, crossings between lattice points and single points
+        ((0, 0), (6, 3)),
+        ((8, 4), (2, 1)),
+        ((1, 0), (2, 3)),
+        ((1, 1), (1, 1)),
+        ((4, 2), (4, 2)),
+        ((9, 9), (9, 9)),
+        ((9, 9), (9, 9)),
+        // More than two infinite lines through a point of overlapping lines
+        ((1, -2), (1, 9)),
+        ((0, 10), (2, 8)),
+    ];
+    let lines: Vec<&PointPair> = lines.iter().collect();
+
+    let mut counts = HashMap::<Point, usize>::new();
+    for line in &lines {
+        for point in LineIter::new(line) {
+            *counts.entry(point).or_default() += 1;
+        }
+    }
+    let expected = counts.values().filter(|count| **count > 1).count();
+    assert_eq!(count_overlapping(&lines), expected);
+
+    // Far too long to rasterize
+    let lines = [
+        ((-1_000_000_000, 0), (1_000_000_000, 0)),
+        ((0, 0), (2_000_000_000, 0)),
+        ((5, -1_000_000_000), (5, 1_000_000_000)),
+    ];
+    let lines: Vec<&PointPair> = lines.iter().collect();
+    assert_eq!(count_overlapping(&lines), 1_000_000_001);
+}
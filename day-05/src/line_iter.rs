@@ -0,0 +1,116 @@
+use crate::{Point, PointPair};
+
+/// Iterates over the points that are exactly on a line, from the first to the second point. For
+/// horizontal, vertical and diagonal lines at 45° these are all points of the line, for other
+/// slopes only some of them.
+///
+/// Divided by their greatest common divisor, the differences of the coordinates are the smallest
+/// step from one of these points to the next. The `i`-th point is the first point plus `i` steps,
+/// so the iterator knows its length and can be iterated from both ends.
+#[derive(Debug, Clone)]
+pub struct LineIter {
+    start: Point,
+    /// Calculated with i64, so that lines across the whole i32 range don't overflow
+    step: (i64, i64),
+    /// Index of the next point from the front
+    front: u64,
+    /// One after the index of the next point from the back
+    back: u64,
+}
+
+impl LineIter {
+    pub fn new(&((x1, y1), (x2, y2)): &PointPair) -> Self {
+        let dx = x2 as i64 - x1 as i64;
+        let dy = y2 as i64 - y1 as i64;
+        let num_steps = gcd(dx.abs(), dy.abs());
+        // A line with a single point takes no steps
+        let step = match num_steps {
+            0 => (0, 0),
+            _ => (dx / num_steps, dy / num_steps),
+        };
+
+        LineIter {
+            start: (x1, y1),
+            step,
+            front: 0,
+            back: num_steps as u64 + 1,
+        }
+    }
+
+    fn point(&self, idx: u64) -> Point {
+        (
+            (self.start.0 as i64 + idx as i64 * self.step.0) as i32,
+            (self.start.1 as i64 + idx as i64 * self.step.1) as i32,
+        )
+    }
+}
+
+impl Iterator for LineIter {
+    type Item = Point;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+        self.front += 1;
+        Some(self.point(self.front - 1))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = (self.back - self.front) as usize;
+        (len, Some(len))
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.front = self.front.saturating_add(n as u64).min(self.back);
+        self.next()
+    }
+
+    fn last(mut self) -> Option<Self::Item> {
+        self.next_back()
+    }
+}
+
+impl DoubleEndedIterator for LineIter {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+        self.back -= 1;
+        Some(self.point(self.back))
+    }
+}
+
+impl ExactSizeIterator for LineIter {}
+
+pub fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+#[test]
+fn test_line_iter() {
+    let points: Vec<Point> = LineIter::new(&((3, 5), (0, 2))).collect();
+    assert_eq!(points, vec![(3, 5), (2, 4), (1, 3), (0, 2)]);
+    let points: Vec<Point> = LineIter::new(&((0, 0), (6, -3))).rev().collect();
+    assert_eq!(points, vec![(6, -3), (4, -2), (2, -1), (0, 0)]);
+    assert_eq!(
+        LineIter::new(&((7, 7), (7, 7))).collect::<Vec<_>>(),
+        vec![(7, 7)]
+    );
+
+    let mut iter = LineIter::new(&((0, 9), (5, 9)));
+    assert_eq!(iter.len(), 6);
+    assert_eq!(iter.nth(1), Some((1, 9)));
+    assert_eq!(iter.next_back(), Some((5, 9)));
+    assert_eq!(iter.len(), 3);
+    assert_eq!(iter.nth(5), None);
+    assert_eq!(iter.len(), 0);
+
+    let iter = LineIter::new(&((i32::MIN, 0), (i32::MAX, 0)));
+    assert_eq!(iter.len(), 1 << 32);
+    assert_eq!(iter.last(), Some((i32::MAX, 0)));
+}
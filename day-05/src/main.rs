@@ -1,8 +1,29 @@
 use std::io::{Read, Result};
 
+mod coverage;
+mod grid;
+mod intersect;
+mod line_iter;
+mod raster;
+mod render;
+
+use coverage::Coverage;
+use line_iter::LineIter;
+use raster::Raster;
+use render::Area;
+
 type Point = (i32, i32);
 type PointPair = (Point, Point);
 
+/// How the overlapping points are counted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Method {
+    /// Mark every point of every line in a grid, split across the given number of threads
+    Rasterize { raster: Raster, threads: usize },
+    /// Intersect the lines with each other, which only counts lattice points
+    Analytic,
+}
+
 fn read_input() -> Result<Vec<PointPair>> {
     let mut input_file = std::fs::File::open("input.txt")?;
     let mut input = String::new();
@@ -29,7 +50,16 @@ fn read_input() -> Result<Vec<PointPair>> {
     Ok(line_points)
 }
 
-fn line_iter(&((x1, y1), (x2, y2)): &PointPair) -> impl Iterator<Item = Point> {
+/// Whether the line is horizontal, vertical or diagonal at 45°, but not a single point.
+fn is_straight_or_diagonal(&((x1, y1), (x2, y2)): &PointPair) -> bool {
+    let dx = (x2 as i64 - x1 as i64).abs();
+    let dy = (y2 as i64 - y1 as i64).abs();
+    (dx == 0) != (dy == 0) || (dx == dy && dx != 0)
+}
+
+/// Only for lines for which `is_straight_or_diagonal` is true. Replaced by `LineIter`, which
+/// doesn't need a heap allocation per line. Kept as a baseline for the benchmark.
+fn boxed_line_iter(&((x1, y1), (x2, y2)): &PointPair) -> impl Iterator<Item = Point> {
     // We have to use a trait object (Box<dyn ...>), because the specific types of the returned
     // iterators differ depending on the input.
     fn coord_iter(c1: i32, c2: i32) -> Box<dyn Iterator<Item = i32>> {
@@ -50,7 +80,11 @@ fn line_iter(&((x1, y1), (x2, y2)): &PointPair) -> impl Iterator<Item = Point> {
     x_iter.zip(y_iter)
 }
 
-fn get_overlapping_points(line_points: &[PointPair], include_diagonals: bool) -> usize {
+fn get_overlapping_points(
+    line_points: &[PointPair],
+    include_diagonals: bool,
+    method: Method,
+) -> usize {
     // use std::collections::HashMap;
     //
     // let mut point_map = HashMap::<Point, i32>::new();
@@ -61,7 +95,7 @@ fn get_overlapping_points(line_points: &[PointPair], include_diagonals: bool) ->
     //     // Only evaluate horizontal and vertical lines, when `include_diagonals` is false.
     //     .filter(|((x1, y1), (x2, y2))| include_diagonals || x1 == x2 || y1 == y2)
     //     .for_each(|point_pair| {
-    //         for p in get_line_iter(point_pair) {
+    //         for p in LineIter::new(point_pair) {
     //             let num_points = point_map.entry(p).or_insert(0);
     //             *num_points += 1;
     //         }
@@ -72,41 +106,211 @@ fn get_overlapping_points(line_points: &[PointPair], include_diagonals: bool) ->
     //     .filter(|num_points| **num_points > 1)
     //     .count();
 
-    // ~1.5x faster but probably uses more memory, because many points won't have any line on it
-    let mut map = [0; 1_000 * 1_000];
-    line_points
+    match method {
+        Method::Rasterize { raster, threads } => {
+            Coverage::with_threads(line_points, include_diagonals, raster, threads)
+                .num_covered_by(2)
+        }
+        Method::Analytic => {
+            // Only evaluate horizontal and vertical lines, when `include_diagonals` is false.
+            let lines: Vec<&PointPair> = line_points
+                .iter()
+                .filter(|((x1, y1), (x2, y2))| include_diagonals || x1 == x2 || y1 == y2)
+                .collect();
+            intersect::count_overlapping(&lines)
+        }
+    }
+}
+
+/// Compares `LineIter` to the boxed iterators, by summing up the points of the lines of the puzzle.
+fn bench(line_points: &[PointPair]) {
+    use std::hint::black_box;
+    use std::time::Instant;
+
+    const RUNS: u32 = 1_000;
+
+    let lines: Vec<&PointPair> = line_points
         .iter()
-        // Only evaluate horizontal and vertical lines, when `include_diagonals` is false.
-        .filter(|((x1, y1), (x2, y2))| include_diagonals || x1 == x2 || y1 == y2)
-        // Even more declarative
-        .flat_map(line_iter)
-        .for_each(|(x, y)| {
-            map[(x + y * 1_000) as usize] += 1;
-        });
-        // .for_each(|point_pair| {
-        //     for (x, y) in line_iter(point_pair) {
-        //         let idx = (1000 * y + x) as usize;
-        //         map[idx] += 1;
-        //     }
-        // });
-
-    let num_overlapping_points = map.iter().filter(|num_points| **num_points > 1).count();
-
-    num_overlapping_points
+        .filter(|line| is_straight_or_diagonal(line))
+        .collect();
+    let sum = |(sum_x, sum_y): (i64, i64), (x, y): Point| (sum_x + x as i64, sum_y + y as i64);
+
+    let start = Instant::now();
+    let mut expected = (0, 0);
+    for _ in 0..RUNS {
+        expected = black_box(&lines)
+            .iter()
+            .flat_map(|line| boxed_line_iter(line))
+            .fold((0, 0), sum);
+    }
+    let boxed_time = start.elapsed() / RUNS;
+
+    let start = Instant::now();
+    for _ in 0..RUNS {
+        let result = black_box(&lines)
+            .iter()
+            .flat_map(|line| LineIter::new(line))
+            .fold((0, 0), sum);
+        assert_eq!(result, expected);
+    }
+    let line_iter_time = start.elapsed() / RUNS;
+
+    println!("Boxed iterators: {:?} per run", boxed_time);
+    println!("LineIter:        {:?} per run", line_iter_time);
 }
 
-fn task_1(line_points: &[PointPair]) {
-    let result = get_overlapping_points(line_points, false);
+fn task_1(line_points: &[PointPair], method: Method) {
+    let result = get_overlapping_points(line_points, false, method);
     println!("Task 1: {}", result);
 }
 
-fn task_2(line_points: &[PointPair]) {
-    let result = get_overlapping_points(line_points, true);
+fn task_2(line_points: &[PointPair], method: Method) {
+    let result = get_overlapping_points(line_points, true, method);
     println!("Task 2: {}", result);
 }
 
 fn main() {
     let line_points = read_input().expect("error reading input");
-    task_1(&line_points);
-    task_2(&line_points);
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    // `--raster bresenham|lattice` picks how lines of other slopes than the ones of the puzzle
+    // are turned into points. Defaults to Bresenham.
+    let mut raster = Raster::Bresenham;
+    if let Some(idx) = args.iter().position(|&arg| arg == "--raster") {
+        match args.get(idx + 1).and_then(|name| Raster::from_name(name)) {
+            Some(parsed) => raster = parsed,
+            None => {
+                eprintln!("invalid raster, expected bresenham or lattice");
+                std::process::exit(1);
+            }
+        }
+        args.drain(idx..idx + 2);
+    }
+
+    // `--threads <n>` rasterizes the lines on n threads. Defaults to one thread.
+    let mut threads = 1;
+    if let Some(idx) = args.iter().position(|&arg| arg == "--threads") {
+        match args
+            .get(idx + 1)
+            .and_then(|n| n.parse().ok())
+            .filter(|&n| n > 0)
+        {
+            Some(n) => threads = n,
+            None => {
+                eprintln!("invalid number of threads, expected a positive number");
+                std::process::exit(1);
+            }
+        }
+        args.drain(idx..idx + 2);
+    }
+
+    // `--analytic` counts the overlaps without rasterizing the lines, which works for lines of any
+    // length. Lines of other slopes than the ones of the puzzle are counted like
+    // `--raster lattice`.
+    let mut method = Method::Rasterize { raster, threads };
+    if let Some(idx) = args.iter().position(|&arg| arg == "--analytic") {
+        method = Method::Analytic;
+        args.remove(idx);
+    }
+
+    // `--crop x1,y1,x2,y2` limits the diagram and heatmap to a rectangle. By default they show the
+    // bounding box of all lines.
+    let mut crop = None;
+    if let Some(idx) = args.iter().position(|&arg| arg == "--crop") {
+        match args.get(idx + 1).and_then(|area| Area::parse(area)) {
+            Some(area) => crop = Some(area),
+            None => {
+                eprintln!("invalid crop, expected x1,y1,x2,y2");
+                std::process::exit(1);
+            }
+        }
+        args.drain(idx..idx + 2);
+    }
+
+    let uses_map = matches!(
+        args.as_slice(),
+        ["at-least" | "max" | "lines-at" | "diagram" | "heatmap", ..]
+    );
+    if method == Method::Analytic && uses_map {
+        eprintln!("--analytic only counts overlaps, the coverage map needs rasterized lines");
+        std::process::exit(1);
+    }
+
+    // The queries use all lines, like task 2
+    match args.as_slice() {
+        [] => {
+            task_1(&line_points, method);
+            task_2(&line_points, method);
+        }
+        // `cargo run --release -- bench` compares the line iterators
+        ["bench"] => bench(&line_points),
+        // `cargo run -- at-least <k>` prints the number of points with at least k lines on them
+        ["at-least", k] => {
+            let Some(k) = k.parse::<u32>().ok().filter(|&k| k > 0) else {
+                eprintln!("expected a positive number of lines");
+                std::process::exit(1);
+            };
+            let coverage = Coverage::with_threads(&line_points, true, raster, threads);
+            println!("{}", coverage.num_covered_by(k));
+        }
+        // `cargo run -- max` prints the highest number of lines on a point and where
+        ["max"] => {
+            let coverage = Coverage::with_threads(&line_points, true, raster, threads);
+            match coverage.max_coverage() {
+                Some((max, points)) => {
+                    let points: Vec<String> =
+                        points.iter().map(|(x, y)| format!("{},{}", x, y)).collect();
+                    println!("{} lines at {}", max, points.join(" "));
+                }
+                None => println!("There are no lines"),
+            }
+        }
+        // `cargo run -- lines-at <x>,<y>` prints the lines on the point, numbered by their line in
+        // the input
+        ["lines-at", point] => {
+            let point = point
+                .split_once(',')
+                .and_then(|(x, y)| Some((x.parse().ok()?, y.parse().ok()?)));
+            let Some(point) = point else {
+                eprintln!("expected a point like 3,4");
+                std::process::exit(1);
+            };
+            let coverage = Coverage::with_threads(&line_points, true, raster, threads);
+            for idx in coverage.lines_at(point) {
+                let ((x1, y1), (x2, y2)) = line_points[idx];
+                println!("Line {}: {},{} -> {},{}", idx + 1, x1, y1, x2, y2);
+            }
+        }
+        // `cargo run -- diagram` prints the diagram of the puzzle, `cargo run -- heatmap <file>`
+        // writes a PGM or PNG image, depending on the extension of the file
+        ["diagram" | "heatmap", ..] => {
+            let coverage = Coverage::with_threads(&line_points, true, raster, threads);
+            let Some(area) = crop.or_else(|| coverage.bounds()) else {
+                println!("There are no lines");
+                return;
+            };
+            let result = match args.as_slice() {
+                ["diagram"] => render::to_text(&coverage, area).map(|text| print!("{}", text)),
+                ["heatmap", file] if file.ends_with(".pgm") => render::to_pgm(&coverage, area)
+                    .and_then(|image| std::fs::write(file, image).map_err(|err| err.to_string())),
+                ["heatmap", file] if file.ends_with(".png") => render::to_png(&coverage, area)
+                    .and_then(|image| std::fs::write(file, image).map_err(|err| err.to_string())),
+                _ => Err(String::from("expected diagram or heatmap <file>.pgm|png")),
+            };
+            if let Err(err) = result {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }
+        }
+        _ => {
+            eprintln!(
+                "usage: day-05 [--raster bresenham|lattice] [--threads <n>] [--analytic] \
+                 [--crop x1,y1,x2,y2] \
+                 [bench | at-least <k> | max | lines-at <x>,<y> | diagram | heatmap <file>.pgm|png]"
+            );
+            std::process::exit(1);
+        }
+    }
 }
@@ -0,0 +1,107 @@
+use crate::{Point, PointPair};
+
+/// How lines that are neither horizontal, vertical nor diagonal at 45° are turned into points.
+/// Both give the same points for those lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Raster {
+    /// The points closest to the line, one per step along the longer axis
+    Bresenham,
+    /// Only the points that are exactly on the line, see `LineIter`
+    Lattice,
+}
+
+impl Raster {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "bresenham" => Some(Raster::Bresenham),
+            "lattice" => Some(Raster::Lattice),
+            _ => None,
+        }
+    }
+}
+
+/// Iterates over the points of a line of any slope with Bresenham's algorithm, from the first to
+/// the second point.
+pub struct Bresenham {
+    x: i32,
+    y: i32,
+    end: Point,
+    step_x: i32,
+    step_y: i32,
+    /// Distances are calculated with i64, so that lines across the whole i32 range don't overflow
+    dx: i64,
+    /// Negative
+    dy: i64,
+    err: i64,
+    done: bool,
+}
+
+impl Bresenham {
+    pub fn new(&((x1, y1), (x2, y2)): &PointPair) -> Self {
+        let dx = (x2 as i64 - x1 as i64).abs();
+        let dy = -(y2 as i64 - y1 as i64).abs();
+
+        Bresenham {
+            x: x1,
+            y: y1,
+            end: (x2, y2),
+            step_x: if x1 < x2 { 1 } else { -1 },
+            step_y: if y1 < y2 { 1 } else { -1 },
+            dx,
+            dy,
+            err: dx + dy,
+            done: false,
+        }
+    }
+}
+
+impl Iterator for Bresenham {
+    type Item = Point;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let point = (self.x, self.y);
+        if point == self.end {
+            self.done = true;
+            return Some(point);
+        }
+
+        let err_2 = 2 * self.err;
+        if err_2 >= self.dy {
+            self.err += self.dy;
+            self.x += self.step_x;
+        }
+        if err_2 <= self.dx {
+            self.err += self.dx;
+            self.y += self.step_y;
+        }
+
+        Some(point)
+    }
+}
+
+#[test]
+fn test_raster() {
+    use crate::line_iter::LineIter;
+
+    let line = ((0, 0), (6, 3));
+    assert_eq!(
+        Bresenham::new(&line).collect::<Vec<_>>(),
+        vec![(0, 0), (1, 1), (2, 1), (3, 2), (4, 2), (5, 3), (6, 3)]
+    );
+    assert_eq!(
+        LineIter::new(&line).collect::<Vec<_>>(),
+        vec![(0, 0), (2, 1), (4, 2), (6, 3)]
+    );
+
+    // Both agree with the puzzle for diagonal lines and single points
+    for line in [((3, 5), (0, 2)), ((4, 4), (4, 4))] {
+        let points: Vec<Point> = Bresenham::new(&line).collect();
+        assert_eq!(points, LineIter::new(&line).collect::<Vec<_>>());
+        assert_eq!(points.first(), Some(&line.0));
+        assert_eq!(points.last(), Some(&line.1));
+    }
+}
@@ -0,0 +1,220 @@
+use crate::coverage::Coverage;
+use crate::Point;
+
+/// Diagrams and images with more points than this are not rendered.
+const MAX_POINTS: i64 = 1 << 24;
+
+/// A rectangle of points, including its borders.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Area {
+    pub min: Point,
+    pub max: Point,
+}
+
+impl Area {
+    /// Parses `x1,y1,x2,y2` with any two opposite corners.
+    pub fn parse(input: &str) -> Option<Self> {
+        let coords: Vec<i32> = input
+            .split(',')
+            .map(|s| s.parse().ok())
+            .collect::<Option<_>>()?;
+        let [x1, y1, x2, y2] = coords[..] else {
+            return None;
+        };
+        Some(Area {
+            min: (x1.min(x2), y1.min(y2)),
+            max: (x1.max(x2), y1.max(y2)),
+        })
+    }
+
+    /// Width and height, if the area isn't too large to be rendered.
+    fn size(&self) -> Result<(i64, i64), String> {
+        // Calculated with i64, because the size can exceed i32
+        let width = self.max.0 as i64 - self.min.0 as i64 + 1;
+        let height = self.max.1 as i64 - self.min.1 as i64 + 1;
+        match width.checked_mul(height) {
+            Some(num_points) if num_points <= MAX_POINTS => Ok((width, height)),
+            _ => Err(format!(
+                "{}x{} points are too many, try cropping the map",
+                width, height
+            )),
+        }
+    }
+
+    /// The points row by row.
+    fn rows(&self) -> impl Iterator<Item = impl Iterator<Item = Point>> {
+        let Area { min, max } = *self;
+        (min.1..=max.1).map(move |y| (min.0..=max.0).map(move |x| (x, y)))
+    }
+}
+
+/// The diagram of the puzzle: `.` for points without a line, the number of lines otherwise, and
+/// `#` for more than nine lines.
+pub fn to_text(coverage: &Coverage, area: Area) -> Result<String, String> {
+    area.size()?;
+
+    let mut text = String::new();
+    for row in area.rows() {
+        for point in row {
+            text.push(match coverage.get(point) {
+                0 => '.',
+                count @ 1..=9 => char::from_digit(count, 10).unwrap(),
+                _ => '#',
+            });
+        }
+        text.push('\n');
+    }
+    Ok(text)
+}
+
+/// Grayscale pixels row by row, from black for points without a line to white for the points with
+/// the most lines in the area.
+fn to_pixels(coverage: &Coverage, area: Area) -> Result<Vec<u8>, String> {
+    area.size()?;
+
+    let counts: Vec<u32> = area.rows().flatten().map(|p| coverage.get(p)).collect();
+    let max = counts.iter().copied().max().unwrap_or(0).max(1);
+    Ok(counts
+        .into_iter()
+        .map(|count| (count as u64 * 255 / max as u64) as u8)
+        .collect())
+}
+
+/// A binary PGM image, which most image viewers can open.
+pub fn to_pgm(coverage: &Coverage, area: Area) -> Result<Vec<u8>, String> {
+    let (width, height) = area.size()?;
+    let mut image = format!("P5\n{} {}\n255\n", width, height).into_bytes();
+    image.extend(to_pixels(coverage, area)?);
+    Ok(image)
+}
+
+/// A grayscale PNG image. There is no compression, as deflate would need a lot more code: the
+/// pixels are stored as they are in deflate blocks that aren't compressed.
+pub fn to_png(coverage: &Coverage, area: Area) -> Result<Vec<u8>, String> {
+    let (width, height) = area.size()?;
+    let pixels = to_pixels(coverage, area)?;
+
+    // Every row starts with its filter type, which is 0 for no filter
+    let mut scanlines = Vec::with_capacity(pixels.len() + height as usize);
+    for row in pixels.chunks(width as usize) {
+        scanlines.push(0);
+        scanlines.extend(row);
+    }
+
+    // A zlib stream with the default window size and no compression
+    let mut zlib = vec![0x78, 0x01];
+    let mut blocks = scanlines.chunks(u16::MAX as usize).peekable();
+    while let Some(block) = blocks.next() {
+        let len = block.len() as u16;
+        zlib.push(blocks.peek().is_none() as u8);
+        zlib.extend(len.to_le_bytes());
+        zlib.extend((!len).to_le_bytes());
+        zlib.extend(block);
+    }
+    zlib.extend(adler32(&scanlines).to_be_bytes());
+
+    let mut header = vec![];
+    header.extend((width as u32).to_be_bytes());
+    header.extend((height as u32).to_be_bytes());
+    // 8 bits per pixel, grayscale, the only compression, filter and interlace methods there are
+    header.extend([8, 0, 0, 0, 0]);
+
+    let mut image = vec![0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+    png_chunk(&mut image, b"IHDR", &header);
+    png_chunk(&mut image, b"IDAT", &zlib);
+    png_chunk(&mut image, b"IEND", &[]);
+    Ok(image)
+}
+
+fn png_chunk(image: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    image.extend((data.len() as u32).to_be_bytes());
+    image.extend(kind);
+    image.extend(data);
+    let crc = crc32(kind.iter().chain(data));
+    image.extend(crc.to_be_bytes());
+}
+
+/// CRC-32 as used by PNG, one bit at a time instead of with a table.
+fn crc32<'a>(bytes: impl Iterator<Item = &'a u8>) -> u32 {
+    let mut crc = u32::MAX;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    const MOD: u32 = 65_521;
+    let (mut a, mut b) = (1, 0);
+    for &byte in bytes {
+        a = (a + byte as u32) % MOD;
+        b = (b + a) % MOD;
+    }
+    b << 16 | a
+}
+
+#[test]
+fn test_render() {
+    use crate::raster::Raster;
+
+    let lines = [
+        ((0, 9), (5, 9)),
+        ((8, 0), (0, 8)),
+        ((9, 4), (3, 4)),
+        ((2, 2), (2, 1)),
+        ((7, 0), (7, 4)),
+        ((6, 4), (2, 0)),
+        ((0, 9), (2, 9)),
+        ((3, 4), (1, 4)),
+        ((0, 0), (8, 8)),
+        ((5, 5), (8, 2)),
+    ];
+    let coverage = Coverage::with_threads(&lines, true, Raster::Bresenham, 1);
+
+    // The diagram of the puzzle
+    let area = coverage.bounds().unwrap();
+    assert_eq!(
+        to_text(&coverage, area).unwrap(),
+        "1.1....11.\n\
+         .111...2..\n\
+         ..2.1.111.\n\
+         ...1.2.2..\n\
+         .112313211\n\
+         ...1.2....\n\
+         ..1...1...\n\
+         .1.....1..\n\
+         1.......1.\n\
+         222111....\n"
+    );
+    let area = Area::parse("4,5,2,4").unwrap();
+    assert_eq!(to_text(&coverage, area).unwrap(), "123\n.1.\n");
+
+    let pgm = to_pgm(&coverage, area).unwrap();
+    assert_eq!(pgm, b"P5\n3 2\n255\n\x55\xaa\xff\x00\x55\x00");
+
+    let png = to_png(&coverage, area).unwrap();
+    assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+    // IHDR with a width of 3 and a height of 2
+    assert_eq!(&png[8..16], b"\0\0\0\x0dIHDR");
+    assert_eq!(&png[16..29], b"\0\0\0\x03\0\0\0\x02\x08\0\0\0\0");
+    // IDAT with the zlib header, the final stored block with LEN 8 and NLEN !8, and the scanlines
+    assert_eq!(&png[33..41], b"\0\0\0\x13IDAT");
+    assert_eq!(&png[41..48], b"\x78\x01\x01\x08\x00\xf7\xff");
+    assert_eq!(&png[48..56], b"\0\x55\xaa\xff\0\x00\x55\x00");
+    assert_eq!(&png[png.len() - 12..], b"\0\0\0\0IEND\xae\x42\x60\x82");
+
+    // Too many points to render, even if the number of points overflows
+    let full = Area::parse("-2147483648,-2147483648,2147483647,2147483647").unwrap();
+    assert!(to_text(&coverage, full).is_err());
+    assert!(to_text(&coverage, Area::parse("0,0,4096,4096").unwrap()).is_err());
+
+    assert_eq!(crc32(b"IEND".iter()), 0xae42_6082);
+    assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+}
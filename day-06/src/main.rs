@@ -1,5 +1,9 @@
 use std::io::{Read, Result};
 
+mod matrix;
+
+use matrix::Arithmetic;
+
 type Population = [u64; 9];
 
 fn read_input() -> Result<Population> {
@@ -45,6 +49,43 @@ fn task_2(population: Population) {
 
 fn main() {
     let population = read_input().expect("error reading input");
-    task_1(population);
-    task_2(population);
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    match args.as_slice() {
+        [] => {
+            task_1(population);
+            task_2(population);
+        }
+        // `cargo run -- days <n> [--mod <m>]` prints the number of fish after n days, which can be
+        // millions of days. Without a modulus, the number has to fit into 64 bits.
+        ["days", days] | ["days", days, "--mod", _] => {
+            let arithmetic = match args.as_slice() {
+                [.., "--mod", modulus] => match modulus.parse() {
+                    Ok(modulus) if modulus > 0 => Arithmetic::Modulo(modulus),
+                    _ => {
+                        eprintln!("expected a positive modulus");
+                        std::process::exit(1);
+                    }
+                },
+                _ => Arithmetic::Checked,
+            };
+            let Ok(days) = days.parse() else {
+                eprintln!("expected a number of days");
+                std::process::exit(1);
+            };
+
+            let total = matrix::simulate(population, days, arithmetic)
+                .and_then(|population| matrix::total(&population, arithmetic));
+            match total {
+                Some(total) => println!("{}", total),
+                None => println!("The number of fish doesn't fit into 64 bits, try --mod <m>"),
+            }
+        }
+        _ => {
+            eprintln!("usage: day-06 [days <n> [--mod <m>]]");
+            std::process::exit(1);
+        }
+    }
 }
@@ -0,0 +1,151 @@
+use crate::Population;
+
+/// Row `i` tells how many fish with timer `i` there are after a day, for every timer of the day
+/// before. `None` for numbers that don't fit into 64 bits with `Arithmetic::Checked`, so they only
+/// fail the result if there are fish with that timer.
+type Matrix = [[Option<u64>; 9]; 9];
+
+/// How sums and products of numbers of fish are calculated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arithmetic {
+    /// Fails when a number doesn't fit into 64 bits, which happens after about 450 days
+    Checked,
+    /// Modulo the number, so that any number of days can be simulated
+    Modulo(u64),
+}
+
+impl Arithmetic {
+    fn add(self, a: u64, b: u64) -> Option<u64> {
+        match self {
+            Arithmetic::Checked => a.checked_add(b),
+            Arithmetic::Modulo(m) => Some(((a as u128 + b as u128) % m as u128) as u64),
+        }
+    }
+
+    fn mul(self, a: u64, b: u64) -> Option<u64> {
+        match self {
+            Arithmetic::Checked => a.checked_mul(b),
+            Arithmetic::Modulo(m) => Some((a as u128 * b as u128 % m as u128) as u64),
+        }
+    }
+
+    fn reduce(self, a: u64) -> u64 {
+        match self {
+            Arithmetic::Checked => a,
+            Arithmetic::Modulo(m) => a % m,
+        }
+    }
+}
+
+/// One day of `simulate_day` as a matrix.
+fn transition() -> Matrix {
+    let mut matrix = [[Some(0); 9]; 9];
+    for (timer, row) in matrix.iter_mut().enumerate().take(8) {
+        row[timer + 1] = Some(1);
+    }
+    // Fish with timer 0 reset to 6 and spawn a new fish with timer 8
+    matrix[6][0] = Some(1);
+    matrix[8][0] = Some(1);
+    matrix
+}
+
+fn identity() -> Matrix {
+    let mut matrix = [[Some(0); 9]; 9];
+    for (i, row) in matrix.iter_mut().enumerate() {
+        row[i] = Some(1);
+    }
+    matrix
+}
+
+/// Product of two numbers of a matrix, where zero times a number that doesn't fit is still zero.
+fn mul_entries(a: Option<u64>, b: Option<u64>, arithmetic: Arithmetic) -> Option<u64> {
+    match (a, b) {
+        (Some(0), _) | (_, Some(0)) => Some(0),
+        (Some(a), Some(b)) => arithmetic.mul(a, b),
+        _ => None,
+    }
+}
+
+fn mul(a: &Matrix, b: &Matrix, arithmetic: Arithmetic) -> Matrix {
+    let mut product = [[Some(0); 9]; 9];
+    for i in 0..9 {
+        for j in 0..9 {
+            for k in 0..9 {
+                let term = mul_entries(a[i][k], b[k][j], arithmetic);
+                product[i][j] = product[i][j]
+                    .zip(term)
+                    .and_then(|(sum, term)| arithmetic.add(sum, term));
+            }
+        }
+    }
+    product
+}
+
+/// `matrix` to the power of `exp` by squaring, with a logarithmic number of multiplications.
+fn pow(mut matrix: Matrix, mut exp: u64, arithmetic: Arithmetic) -> Matrix {
+    let mut result = identity();
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = mul(&result, &matrix, arithmetic);
+        }
+        exp >>= 1;
+        // The last square isn't needed
+        if exp > 0 {
+            matrix = mul(&matrix, &matrix, arithmetic);
+        }
+    }
+    result
+}
+
+/// Same as `simulate`, but in logarithmic time, as `days` days are the transition matrix to the
+/// power of `days`. `None` if a number overflows with `Arithmetic::Checked`.
+pub fn simulate(population: Population, days: u64, arithmetic: Arithmetic) -> Option<Population> {
+    let matrix = pow(transition(), days, arithmetic);
+    let mut new_population = [0; 9];
+    for (timer, row) in matrix.iter().enumerate() {
+        for (count, &factor) in population.iter().zip(row) {
+            let term = mul_entries(factor, Some(arithmetic.reduce(*count)), arithmetic)?;
+            new_population[timer] = arithmetic.add(new_population[timer], term)?;
+        }
+    }
+    Some(new_population)
+}
+
+/// Total number of fish. `None` if it overflows with `Arithmetic::Checked`.
+pub fn total(population: &Population, arithmetic: Arithmetic) -> Option<u64> {
+    population
+        .iter()
+        .try_fold(0, |total, &count| arithmetic.add(total, count))
+}
+
+#[test]
+fn test_simulate() {
+    let mut population = [0, 1, 1, 2, 1, 0, 0, 0, 0];
+    for days in 0..300 {
+        assert_eq!(
+            simulate([0, 1, 1, 2, 1, 0, 0, 0, 0], days, Arithmetic::Checked),
+            Some(population)
+        );
+        population = crate::simulate_day(population);
+    }
+
+    let population = [0, 1, 1, 2, 1, 0, 0, 0, 0];
+    let modulo = Arithmetic::Modulo(1_000);
+    let expected = total(&crate::simulate(population, 256), Arithmetic::Checked).unwrap();
+    let result = simulate(population, 256, modulo).unwrap();
+    assert_eq!(total(&result, modulo), Some(expected % 1_000));
+
+    assert_eq!(simulate(population, 1_000, Arithmetic::Checked), None);
+    assert!(simulate(population, 1_000_000_000_000, modulo).is_some());
+
+    // Overflows only count for timers that have fish
+    let empty = [0; 9];
+    assert_eq!(simulate(empty, 1_000, Arithmetic::Checked), Some(empty));
+    // The descendants of a fish with timer 0 don't fit after 530 days, but those of timer 8 do
+    let population = [0, 0, 0, 0, 0, 0, 0, 0, 1];
+    let expected = (0..530).fold(population, |population, _| crate::simulate_day(population));
+    assert_eq!(
+        simulate(population, 530, Arithmetic::Checked),
+        Some(expected)
+    );
+}